@@ -0,0 +1,76 @@
+// Steps the fluid solver without a window and prints a summary of the final fields.
+//
+// Usage: cargo run --release --example headless -- [--steps N] [--size WxH] [--script FILE] [--fallback]
+//
+// Script lines are "<step> <from_x> <from_y> <to_x> <to_y>" in grid coordinates.
+// Without a script, a single horizontal stroke across the middle of the canvas is painted.
+use fluid_sim_paint::gui_mod::gui::GuiParams;
+use fluid_sim_paint::headless::{BrushEvent, HeadlessSim, parse_script};
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+
+    let mut steps = 120;
+    let mut width = 256;
+    let mut height = 256;
+    let mut script_path = None;
+    let mut force_fallback_adapter = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--steps" => steps = args.next().unwrap_or_default().parse()?,
+            "--size" => {
+                let size = args.next().unwrap_or_default();
+                let (w, h) = size
+                    .split_once('x')
+                    .ok_or_else(|| anyhow::anyhow!("--size expects WxH, got '{size}'"))?;
+                width = w.parse()?;
+                height = h.parse()?;
+            }
+            "--script" => script_path = args.next(),
+            "--fallback" => force_fallback_adapter = true,
+            other => anyhow::bail!("unknown argument '{other}'"),
+        }
+    }
+
+    let script = match script_path {
+        Some(path) => parse_script(&std::fs::read_to_string(path)?)?,
+        None => {
+            let y = height as f32 / 2.0;
+            (0..10)
+                .map(|i| BrushEvent {
+                    step: i,
+                    from: [width as f32 * (0.2 + 0.06 * i as f32), y],
+                    to: [width as f32 * (0.26 + 0.06 * i as f32), y],
+                })
+                .collect()
+        }
+    };
+
+    let mut headless = pollster::block_on(HeadlessSim::new(width, height, force_fallback_adapter))?;
+    let params = GuiParams::default();
+    headless.run(&params, steps, &script);
+
+    let density = headless.read_density();
+    let velocity = headless.read_velocity();
+
+    let total_ink: f32 = density.iter().map(|cell| cell[3]).sum();
+    let max_speed = velocity
+        .iter()
+        .map(|v| (v[0] * v[0] + v[1] * v[1]).sqrt())
+        .fold(0.0, f32::max);
+
+    println!("steps:     {}", headless.steps_taken);
+    println!("grid:      {width}x{height}");
+    println!("total ink: {total_ink:.3}");
+    println!("max speed: {max_speed:.3}");
+    // The readback just above waited for the GPU, so the last measurement has usually landed
+    if let Some(stats) = headless.divergence_stats() {
+        println!(
+            "divergence: rms {:.3e}, max {:.3e} ({} it.)",
+            stats.rms, stats.max, stats.iterations
+        );
+    }
+    Ok(())
+}
@@ -1,19 +1,30 @@
 use super::fluid_sim::FluidSim;
+use super::history::History;
+use super::pigment::{PALETTE, PALETTE_SIZE, pigment_optics};
 use super::pipelines::draw::record_render_pass;
-use super::pipelines::render_pipeline::{ViewUniforms, create_render_setup};
+use super::pipelines::render_pipeline::{PaletteUniforms, ViewUniforms, create_render_setup};
 use super::resources::quad::create_canvas_quad;
-use crate::gui_mod::gui::GuiParams;
+use super::resources::texture::Texture;
+use crate::gui_mod::gui::{ColorModel, GuiParams};
 use crate::state::InteractionState;
 use wgpu::util::DeviceExt;
-use wgpu::{BindGroup, Buffer, CommandEncoder, Device, Queue, RenderPipeline, TextureView};
+use wgpu::{
+    BindGroup, BindGroupLayout, Buffer, CommandEncoder, Device, Queue, RenderPipeline, TextureView,
+};
 
 pub struct Canvas {
     pub sim: FluidSim, // Public so State can query width/height if needed
 
     // Renderer Internals
     render_pipeline: RenderPipeline,
+    render_layout: BindGroupLayout,
     render_bind_groups: Vec<BindGroup>,
     view_buffer: Buffer,
+    palette_buffer: Buffer, // Optical constants of the palette pigments, never changes
+    // Imported obstacle mask waiting to be applied, drawn over the canvas
+    mask_preview: Texture,
+    show_mask_preview: bool,
+    history: History, // Undo snapshots of the sim
     vertex_buffer: Buffer,
     index_buffer: Buffer,
     num_indices: u32,
@@ -39,7 +50,12 @@ impl Canvas {
             canvas_size: [width as f32, height as f32],
             pan: [0.0, 0.0],
             zoom: default_zoom,
-            _padding: 0,
+            show_obstacles: 1,
+            show_mask_preview: 0,
+            color_model: 0,
+            grain_shading: 0.0,
+            rotation: 0.0,
+            paper_color: [1.0; 4],
         };
 
         let view_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -48,125 +64,194 @@ impl Canvas {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
+        let mut palette = PaletteUniforms {
+            absorption: [[0.0; 4]; PALETTE_SIZE],
+            scattering: [[0.0; 4]; PALETTE_SIZE],
+        };
+        for (i, pigment) in PALETTE.iter().enumerate() {
+            let (k, s) = pigment_optics(pigment);
+            palette.absorption[i] = [k[0], k[1], k[2], 0.0];
+            palette.scattering[i] = [s[0], s[1], s[2], 0.0];
+        }
+        let palette_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Palette Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[palette]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
         // Setup Render Pipeline
         let (render_pipeline, render_layout) = create_render_setup(device, config);
 
+        let mask_preview = create_mask_preview(device, width, height);
+
         // Create Render Bind Groups
         // CRITICAL: We bind to the textures OWNED by 'sim'
-        let create_render_bg = |tex: &super::resources::texture::Texture| -> BindGroup {
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Render Group"),
-                layout: &render_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&tex.view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&tex.sampler),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: view_buffer.as_entire_binding(),
-                    },
-                ],
-            })
-        };
-
-        let render_bind_groups = vec![
-            create_render_bg(&sim.density_a),
-            // create_render_bg(&sim.density_b),
-        ];
+        let render_bind_groups = create_render_bind_groups(
+            device,
+            &render_layout,
+            &sim,
+            &view_buffer,
+            &palette_buffer,
+            &mask_preview,
+        );
 
         Self {
             sim,
             render_pipeline,
+            render_layout,
             render_bind_groups,
             view_buffer,
+            palette_buffer,
+            mask_preview,
+            show_mask_preview: false,
+            history: History::default(),
             vertex_buffer,
             index_buffer,
             num_indices,
         }
     }
 
-    // PHASE 1: PHYSICS & LOGIC
-    // This updates the state of the world (Sim).
-    pub fn update(
+    // Reallocates the simulation at a new grid size. The current ink and velocity are
+    // resampled into the new grid, and every bind group pointing at the old textures is rebuilt.
+    pub fn resize_sim(
         &mut self,
-        queue: &Queue,
+        device: &Device,
         encoder: &mut CommandEncoder,
-        input: &InteractionState,
-        params: &GuiParams,
-        screen_size: (u32, u32),
+        width: u32,
+        height: u32,
     ) {
-        if input.clear_requested {
-            self.sim.clear(encoder);
+        if width == self.sim.width && height == self.sim.height {
+            return;
         }
+        self.sim = self.sim.resized(device, encoder, width, height);
+        // Snapshots of the old size can't be restored into the new textures
+        self.history.clear();
+        // A pending preview was placed for the old size; the owner uploads it again
+        self.mask_preview = create_mask_preview(device, width, height);
+        self.show_mask_preview = false;
+        self.render_bind_groups = create_render_bind_groups(
+            device,
+            &self.render_layout,
+            &self.sim,
+            &self.view_buffer,
+            &self.palette_buffer,
+            &self.mask_preview,
+        );
+    }
 
-        // 1. Diffuse (Spread Ink) <--- NEW STEP
-        // Add a 'diffusion' slider to your GuiParams!
-        // For now, hardcode 0.001 to test.
-        self.sim.diffuse(queue, encoder, params);
+    // Shows an imported mask (one value per sim cell, 1.0 = solid) over the canvas,
+    // or hides it with None. Purely visual: the sim doesn't see it until it is uploaded.
+    pub fn set_mask_preview(&mut self, queue: &Queue, cells: Option<&[f32]>) {
+        if let Some(cells) = cells {
+            self.mask_preview
+                .write_all(queue, bytemuck::cast_slice(cells));
+        }
+        self.show_mask_preview = cells.is_some();
+    }
 
-        // 2. Step 1: Advect (A -> B)
-        self.sim.advect(queue, encoder, params);
+    // UNDO: call before a change that should be undoable (a stroke, a clear).
+    // Recorded into 'encoder', so it sees the sim as the commands before it leave it.
+    pub fn snapshot(&mut self, device: &Device, encoder: &mut CommandEncoder, params: &GuiParams) {
+        let layers = self
+            .sim
+            .history_layers(params.color_model == ColorModel::Palette);
+        self.history
+            .push(device, encoder, &layers, undo_budget(params));
+    }
 
-        // 3. Step 2: Brush (B -> A)
-        // If mouse is pressed, we run the brush shader which reads B and writes A.
-        if input.mouse_pressed {
-            self.apply_brush(queue, encoder, input, params, screen_size);
-        } else {
-            // CRITICAL: If mouse is NOT pressed, we still need to get data from B back to A!
-            // Otherwise A stays stale.
-            // For now, simple hack: Just run the brush with 0 strength/radius?
-            // BETTER: Just copy B back to A using a simple copy command.
-            encoder.copy_texture_to_texture(
-                self.sim.density_b.texture.as_image_copy(),
-                self.sim.density_a.texture.as_image_copy(),
-                self.sim.density_a.texture.size(),
-            );
-            encoder.copy_texture_to_texture(
-                self.sim.velocity_b.texture.as_image_copy(),
-                self.sim.velocity_a.texture.as_image_copy(),
-                self.sim.velocity_a.texture.size(),
-            );
-        }
+    pub fn undo(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        params: &GuiParams,
+    ) -> bool {
+        let layers = self
+            .sim
+            .history_layers(params.color_model == ColorModel::Palette);
+        self.history
+            .undo(device, encoder, &layers, undo_budget(params))
+    }
+
+    pub fn redo(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        params: &GuiParams,
+    ) -> bool {
+        let layers = self
+            .sim
+            .history_layers(params.color_model == ColorModel::Palette);
+        self.history
+            .redo(device, encoder, &layers, undo_budget(params))
+    }
 
-        // 4. Project (Clean up Velocity A -> B -> A)
-        // This makes the liquid swirly!
-        self.sim.project(encoder);
+    // Forget every snapshot, e.g. once the painting has been converted to another colour model
+    pub fn clear_history(&mut self) {
+        self.history.clear();
     }
 
-    // Helper: Logic to apply forces (Just the math/dispatch)
-    fn apply_brush(
+    // (undo steps, redo steps) available
+    pub fn history_steps(&self) -> (usize, usize) {
+        self.history.steps()
+    }
+
+    // PHASE 1: PHYSICS & LOGIC
+    // This updates the state of the world (Sim) by 'substeps' steps of 'dt' each.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
         &mut self,
         queue: &Queue,
         encoder: &mut CommandEncoder,
         input: &InteractionState,
         params: &GuiParams,
         screen_size: (u32, u32),
+        substeps: u32,
+        dt: f32,
     ) {
-        // Coordinate Transformation (Screen -> Grid)
-        let to_grid = |screen_pos: [f32; 2]| -> [f32; 2] {
-            let screen_center_x = screen_size.0 as f32 / 2.0;
-            let screen_center_y = screen_size.1 as f32 / 2.0;
-            let offset_x = screen_pos[0] - screen_center_x;
-            let offset_y = screen_pos[1] - screen_center_y;
-            let zoom = params.zoom_level;
-            let grid_center_x = self.sim.width as f32 / 2.0;
-            let grid_center_y = self.sim.height as f32 / 2.0;
-            [
-                grid_center_x + (offset_x / zoom),
-                grid_center_y + (offset_y / zoom),
-            ]
+        // Clearing doesn't wait for the next step (it still works while paused)
+        if substeps == 0 {
+            if input.clear_requested {
+                self.sim.clear(encoder);
+            }
+            return;
+        }
+
+        // Brush (B -> A) only runs while the mouse is held; otherwise the sim copies B back to A.
+        let brush = if input.mouse_pressed && !input.stroke.is_empty() {
+            Some(self.brush_stroke(input, params, screen_size))
+        } else {
+            None
         };
 
-        let current_grid = to_grid(input.mouse_pos);
-        let last_grid = to_grid(input.last_mouse_pos);
+        // The whole stroke goes into the last substep (the brush buffers can only hold one
+        // stroke per submit)
+        for i in 0..substeps {
+            let is_last = i + 1 == substeps;
+            self.sim.step(
+                queue,
+                encoder,
+                params,
+                brush.as_deref().filter(|_| is_last),
+                input.clear_requested && i == 0,
+                dt,
+            );
+        }
+    }
 
-        self.sim
-            .add_forces(queue, encoder, current_grid, last_grid, params);
+    // Helper: Map the (stabilized) stroke from screen space into grid space, with the same
+    // transform the canvas is drawn with
+    fn brush_stroke(
+        &self,
+        input: &InteractionState,
+        params: &GuiParams,
+        screen_size: (u32, u32),
+    ) -> Vec<[f32; 2]> {
+        let grid_size = (self.sim.width, self.sim.height);
+        input
+            .stroke
+            .iter()
+            .map(|&pos| params.view.screen_to_grid(pos, screen_size, grid_size))
+            .collect()
     }
 
     // PHASE 2: DRAWING
@@ -183,9 +268,19 @@ impl Canvas {
         let current_uniforms = ViewUniforms {
             screen_size: [screen_size.0 as f32, screen_size.1 as f32],
             canvas_size: [self.sim.width as f32, self.sim.height as f32],
-            pan: [0.0, 0.0],
-            zoom: params.zoom_level,
-            _padding: 0,
+            pan: params.view.pan,
+            zoom: params.view.zoom,
+            show_obstacles: u32::from(params.show_obstacles),
+            show_mask_preview: u32::from(self.show_mask_preview),
+            color_model: params.color_model as u32,
+            grain_shading: params.grain_shading,
+            rotation: params.view.rotation,
+            paper_color: [
+                params.paper_color[0],
+                params.paper_color[1],
+                params.paper_color[2],
+                1.0,
+            ],
         };
         queue.write_buffer(
             &self.view_buffer,
@@ -204,3 +299,81 @@ impl Canvas {
         );
     }
 }
+
+fn create_render_bind_groups(
+    device: &Device,
+    layout: &BindGroupLayout,
+    sim: &FluidSim,
+    view_buffer: &Buffer,
+    palette_buffer: &Buffer,
+    mask_preview: &Texture,
+) -> Vec<BindGroup> {
+    let create_render_bg = |tex: &Texture| -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Render Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&tex.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&tex.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: view_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&sim.obstacles.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&mask_preview.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(&sim.density2_a.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: palette_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: wgpu::BindingResource::TextureView(&sim.paper.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: wgpu::BindingResource::TextureView(&sim.paper2.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 9,
+                    resource: wgpu::BindingResource::TextureView(&sim.grain.view),
+                },
+            ],
+        })
+    };
+
+    vec![
+        create_render_bg(&sim.density_a),
+        // create_render_bg(&sim.density_b),
+    ]
+}
+
+fn create_mask_preview(device: &Device, width: u32, height: u32) -> Texture {
+    Texture::create_storage_texture(
+        device,
+        width,
+        height,
+        wgpu::TextureFormat::R32Float,
+        Some("Mask Preview"),
+    )
+}
+
+// The undo memory budget in bytes
+fn undo_budget(params: &GuiParams) -> u64 {
+    u64::from(params.undo_budget_mb) * 1024 * 1024
+}
@@ -0,0 +1,224 @@
+use super::pipelines::reduce_pipeline::ReducePipeline;
+use super::resources::texture::Texture;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use wgpu::{BindGroup, BufferAsyncError, CommandEncoder, Device};
+
+// How far the projected velocity is from divergence-free, for one measured step
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DivergenceStats {
+    pub rms: f32, // L2 norm, normalised by the cell count
+    pub max: f32, // L-infinity norm
+    // Solver work used for the measured step (iterations, or V-cycles for multigrid)
+    pub iterations: u32,
+}
+
+enum Readback {
+    Idle,
+    // The reduction and the copy into the staging buffer are recorded but not yet submitted
+    Recorded {
+        iterations: u32,
+    },
+    // map_async has been requested; the receiver fires once the GPU is done
+    Mapping {
+        iterations: u32,
+        receiver: Receiver<Result<(), BufferAsyncError>>,
+    },
+}
+
+// Reduces the post-projection divergence on the GPU and reads the result back WITHOUT stalling:
+// at most one measurement is in flight, and frames recorded meanwhile just skip it.
+pub struct DivergenceMonitor {
+    pipeline: ReducePipeline,
+    partial_bind_group: BindGroup,
+    final_bind_group: BindGroup,
+    x_groups: u32,
+    y_groups: u32,
+    cell_count: f32,
+    result_buffer: wgpu::Buffer,
+    staging_buffer: wgpu::Buffer,
+    readback: Readback,
+    latest: Option<DivergenceStats>,
+    // Set when a reading arrives, cleared once the adaptive budget has reacted to it
+    unseen: Option<DivergenceStats>,
+    budget: Option<u32>,
+}
+
+impl DivergenceMonitor {
+    // 'field' is the R32Float divergence texture (width x height) the check pass writes into
+    pub fn new(device: &Device, field: &Texture, width: u32, height: u32) -> Self {
+        let pipeline = ReducePipeline::new(device);
+
+        let x_groups = (width as f32 / 16.0).ceil() as u32;
+        let y_groups = (height as f32 / 16.0).ceil() as u32;
+
+        // One vec2<f32> (sum of squares, max) per workgroup
+        let partials_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Reduce Partials"),
+            size: (x_groups * y_groups) as wgpu::BufferAddress * 8,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let result_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Reduce Result"),
+            size: 16,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Reduce Staging"),
+            size: 16,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let partial_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Reduce Partial BG"),
+            layout: &pipeline.partial_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&field.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: partials_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        let final_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Reduce Final BG"),
+            layout: &pipeline.final_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: partials_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: result_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Self {
+            pipeline,
+            partial_bind_group,
+            final_bind_group,
+            x_groups,
+            y_groups,
+            cell_count: (width * height) as f32,
+            result_buffer,
+            staging_buffer,
+            readback: Readback::Idle,
+            latest: None,
+            unseen: None,
+            budget: None,
+        }
+    }
+
+    // True when a new measurement can be recorded this frame
+    pub fn is_idle(&self) -> bool {
+        matches!(self.readback, Readback::Idle)
+    }
+
+    // Records the reduction of the field and the copy into the staging buffer.
+    // Call only when is_idle(), after the pass that fills the field.
+    pub fn record(&mut self, encoder: &mut CommandEncoder, iterations: u32) {
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Reduce Partial Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline.partial_pipeline);
+            pass.set_bind_group(0, &self.partial_bind_group, &[]);
+            pass.dispatch_workgroups(self.x_groups, self.y_groups, 1);
+        }
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Reduce Final Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline.final_pipeline);
+            pass.set_bind_group(0, &self.final_bind_group, &[]);
+            pass.dispatch_workgroups(1, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.result_buffer, 0, &self.staging_buffer, 0, 16);
+
+        self.readback = Readback::Recorded { iterations };
+    }
+
+    // Call once the encoder holding record() has been submitted
+    pub fn after_submit(&mut self) {
+        if let Readback::Recorded { iterations } = self.readback {
+            let (sender, receiver) = mpsc::channel();
+            self.staging_buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = sender.send(result);
+                });
+            self.readback = Readback::Mapping {
+                iterations,
+                receiver,
+            };
+        }
+    }
+
+    // Non-blocking: picks up the measurement if the GPU has finished it
+    pub fn poll(&mut self, device: &Device) {
+        let Readback::Mapping {
+            iterations,
+            receiver,
+        } = &self.readback
+        else {
+            return;
+        };
+        let _ = device.poll(wgpu::PollType::Poll);
+
+        match receiver.try_recv() {
+            Err(TryRecvError::Empty) => return,
+            Ok(Ok(())) => {
+                let [sum_sq, max, _, _] = {
+                    let mapped = self.staging_buffer.slice(..).get_mapped_range();
+                    *bytemuck::from_bytes::<[f32; 4]>(&mapped)
+                };
+                self.staging_buffer.unmap();
+
+                let stats = DivergenceStats {
+                    rms: (sum_sq / self.cell_count).sqrt(),
+                    max,
+                    iterations: *iterations,
+                };
+                self.latest = Some(stats);
+                self.unseen = Some(stats);
+            }
+            Ok(Err(e)) => log::warn!("Divergence readback failed: {e}"),
+            Err(TryRecvError::Disconnected) => log::warn!("Divergence readback was dropped"),
+        }
+        self.readback = Readback::Idle;
+    }
+
+    pub fn latest(&self) -> Option<DivergenceStats> {
+        self.latest
+    }
+
+    // Solver work for the next step when iterating to a tolerance.
+    // Readings arrive a frame or two late, so this is a feedback loop rather than an exact stop:
+    // the budget grows while the error is above the tolerance and slowly shrinks once it is
+    // comfortably below. 'limit' (the slider value) is the hard cap.
+    pub fn adaptive_budget(&mut self, limit: u32, tolerance: f32) -> u32 {
+        let limit = limit.max(1);
+        let mut budget = self.budget.unwrap_or(limit).clamp(1, limit);
+
+        if let Some(stats) = self.unseen.take() {
+            if stats.rms > tolerance {
+                budget += (budget / 4).max(1);
+            } else if stats.rms < tolerance * 0.5 {
+                budget -= (budget / 8).max(1).min(budget - 1);
+            }
+            budget = budget.clamp(1, limit);
+        }
+
+        self.budget = Some(budget);
+        budget
+    }
+}
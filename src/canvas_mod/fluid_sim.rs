@@ -1,10 +1,19 @@
+use super::divergence_monitor::{DivergenceMonitor, DivergenceStats};
+use super::multigrid::MultigridSolver;
+use super::pigment::{PALETTE_SIZE, brush_pigment};
 use super::pipelines::advect_pipeline::{AdvectionPipeline, AdvectionUniforms};
 use super::pipelines::brush_pipeline::{BrushPipeline, BrushUniforms};
-use super::pipelines::diffuse_pipeline::{DiffusePipeline, DiffuseUniforms};
-use super::pipelines::pressure_pipeline::PressurePipeline;
+use super::pipelines::diffuse_pipeline::{DiffuseFormat, DiffusePipeline, DiffuseUniforms};
+use super::pipelines::obstacle_layout::create_obstacle_layout;
+use super::pipelines::paper_pipeline::{PaperPipeline, PaperUniforms};
+use super::pipelines::pressure_pipeline::{PressurePipeline, PressureUniforms};
+use super::pipelines::resample_pipeline::ResamplePipeline;
+use super::pipelines::vorticity_pipeline::{VorticityPipeline, VorticityUniforms};
 use super::resources::texture::{Texture, create_sim_textures};
-use crate::gui_mod::gui::GuiParams;
-use wgpu::{BindGroup, CommandEncoder, Device, Queue};
+use super::stroke::{path_length, stroke_bounds, stroke_path};
+use crate::gui_mod::gui::{AdvectionScheme, ColorModel, DiffuseSolver, GuiParams, PressureSolver};
+use crate::timestep::SIM_DT;
+use wgpu::{BindGroup, CommandEncoder, ComputePipeline, Device, Queue};
 
 pub struct FluidSim {
     pub width: u32,
@@ -18,18 +27,48 @@ pub struct FluidSim {
     pub pressure_a: Texture,
     pub pressure_b: Texture,
     pub temp_density: Texture,
+    pub temp_velocity: Texture,
+    pub curl: Texture,
+    pub obstacles: Texture, // Solid cells (1.0), painted with the wall tools
+    // Palette mode: concentrations of pigments 4-7 (density holds 0-3). Idle in the other modes.
+    pub density2_a: Texture,
+    pub density2_b: Texture,
+    pub temp_density2: Texture,
+    // Ink that has settled out of the liquid. Doesn't move; same format as density (and density2).
+    pub paper: Texture,
+    pub paper2: Texture,
+    pub wetness_a: Texture, // 1 = freshly painted, dries to 0
+    pub wetness_b: Texture,
+    pub grain: Texture, // Paper relief (grain.rs), read by advection, settling and the renderer
 
     brush_pipeline: BrushPipeline,
     advect_pipeline: AdvectionPipeline,
     pressure_pipeline: PressurePipeline,
 
-    advect_bind_group: BindGroup, // Reads A -> Writes B
-    brush_bind_group: BindGroup,  // Reads B -> Writes A
+    advect_bind_group: BindGroup,         // Reads A -> Writes B
+    maccormack_bind_group: BindGroup,     // Reads A + B -> Writes Temp
+    advect2_bind_group: BindGroup,        // Second density field, ink only: A -> B
+    maccormack2_bind_group: BindGroup,    // Second density field, ink only: A + B -> Temp
+    brush_bind_group: BindGroup,          // Reads B -> Writes A
+    obstacle_brush_bind_group: BindGroup, // Writes Obstacles
+    obstacle_bind_group: BindGroup,       // group(1) of advect and pressure: Reads Obstacles
     div_bind_group: BindGroup,
+    check_div_bind_group: BindGroup, // Reads the projected velocity (B) -> Writes Div
     jacobi_bind_groups: Vec<BindGroup>, // Needs A->B and B->A
     sub_bind_group: BindGroup,
     diffuse_pipeline: DiffusePipeline,
-    diffuse_bind_groups: Vec<BindGroup>, // Ping-Pong groups
+    diffuse_bind_groups: Vec<BindGroup>,  // Ping-Pong groups
+    diffuse2_bind_groups: Vec<BindGroup>, // Same, for the second density field
+    paper_pipeline: PaperPipeline,
+    paper_bind_group: BindGroup, // Reads Density A + Paper + Wetness A -> Writes B, Temp, B
+    paper2_bind_group: BindGroup, // Same for the second density field (palette mode)
+    viscosity_pipeline: DiffusePipeline, // Same solve on the Rg32Float velocity field
+    viscosity_bind_groups: Vec<BindGroup>,
+    multigrid: MultigridSolver,
+    vorticity_pipeline: VorticityPipeline,
+    curl_bind_group: BindGroup,    // Reads Vel A -> Writes Curl
+    confine_bind_group: BindGroup, // Reads Vel A + Curl -> Writes Vel B
+    divergence_monitor: DivergenceMonitor,
 }
 
 impl FluidSim {
@@ -43,45 +82,113 @@ impl FluidSim {
             pressure_b,
             divergence,
             temp_density,
+            temp_velocity,
         ) = create_sim_textures(device, width, height);
 
+        // OBSTACLES: one mask, read as group(1) by every pass that has to flow around it
+        let obstacles = Texture::create_storage_texture(
+            device,
+            width,
+            height,
+            wgpu::TextureFormat::R32Float,
+            Some("Obstacles"),
+        );
+        let create_density2 = |label: &str| {
+            Texture::create_storage_texture(
+                device,
+                width,
+                height,
+                wgpu::TextureFormat::Rgba32Float,
+                Some(label),
+            )
+        };
+        let density2_a = create_density2("Density 2 A");
+        let density2_b = create_density2("Density 2 B");
+        let temp_density2 = create_density2("Temp Density 2");
+        let paper = create_density2("Paper");
+        let paper2 = create_density2("Paper 2");
+        let create_wetness = |label: &str| {
+            Texture::create_storage_texture(
+                device,
+                width,
+                height,
+                wgpu::TextureFormat::R32Float,
+                Some(label),
+            )
+        };
+        let wetness_a = create_wetness("Wetness A");
+        let wetness_b = create_wetness("Wetness B");
+        // Starts flat (all zero): no grain effects until one is generated or loaded
+        let grain = create_wetness("Paper Grain");
+
+        let obstacle_layout = create_obstacle_layout(device);
+        let obstacle_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Obstacle BG"),
+            layout: &obstacle_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&obstacles.view),
+            }],
+        });
+
         let brush_pipeline = BrushPipeline::new(device);
-        let advect_pipeline = AdvectionPipeline::new(device, width, height);
-        let pressure_pipeline = PressurePipeline::new(device, width, height);
+        let advect_pipeline = AdvectionPipeline::new(device, width, height, &obstacle_layout);
+        let pressure_pipeline = PressurePipeline::new(device, width, height, &obstacle_layout);
+
+        fn texture_entry(binding: u32, texture: &Texture) -> wgpu::BindGroupEntry<'_> {
+            wgpu::BindGroupEntry {
+                binding,
+                resource: wgpu::BindingResource::TextureView(&texture.view),
+            }
+        }
 
         // ADVECTION: Read A -> Write B
-        let advect_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Advect A->B"),
-            layout: &advect_pipeline.bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: advect_pipeline.uniform_buffer.as_entire_binding(),
-                },
-                // Input: A
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&velocity_a.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&density_a.view),
-                },
-                // Output: B
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::TextureView(&velocity_b.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: wgpu::BindingResource::TextureView(&density_b.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 5,
-                    resource: wgpu::BindingResource::Sampler(&density_a.sampler),
-                },
-            ],
-        });
+        let create_advect = |density_in: &Texture, density_out: &Texture| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Advect A->B"),
+                layout: &advect_pipeline.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: advect_pipeline.uniform_buffer.as_entire_binding(),
+                    },
+                    // Input: A
+                    texture_entry(1, &velocity_a),
+                    texture_entry(2, density_in),
+                    // Output: B
+                    texture_entry(3, &velocity_b),
+                    texture_entry(4, density_out),
+                    texture_entry(8, &grain),
+                ],
+            })
+        };
+        let advect_bind_group = create_advect(&density_a, &density_b);
+        // The ink-only passes leave velocity B alone
+        let advect2_bind_group = create_advect(&density2_a, &density2_b);
+
+        // MACCORMACK CORRECTION: Read A (original) + B (forward result) -> Write Temp
+        let create_maccormack =
+            |density_in: &Texture, density_hat: &Texture, density_out: &Texture| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("MacCormack A+B->Temp"),
+                    layout: &advect_pipeline.maccormack_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: advect_pipeline.uniform_buffer.as_entire_binding(),
+                        },
+                        texture_entry(1, &velocity_a),
+                        texture_entry(2, density_in),
+                        texture_entry(3, &temp_velocity),
+                        texture_entry(4, density_out),
+                        texture_entry(6, &velocity_b),
+                        texture_entry(7, density_hat),
+                        texture_entry(8, &grain),
+                    ],
+                })
+            };
+        let maccormack_bind_group = create_maccormack(&density_a, &density_b, &temp_density);
+        let maccormack2_bind_group = create_maccormack(&density2_a, &density2_b, &temp_density2);
 
         // BRUSH: Read B -> Write A
         // This ensures we add ink ON TOP of the advected result
@@ -111,29 +218,44 @@ impl FluidSim {
                     binding: 4,
                     resource: wgpu::BindingResource::TextureView(&velocity_a.view),
                 },
-            ],
-        });
-
-        // Divergence Bind Group (Read Vel A -> Write Div)
-        let div_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Divergence BG"),
-            layout: &pressure_pipeline.div_layout,
-            entries: &[
+                // Pigments 4-7: B -> A
+                texture_entry(5, &density2_b),
+                texture_entry(6, &density2_a),
+                // The stroke wets the paper (only the cells under it are written)
+                texture_entry(7, &wetness_a),
                 wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: pressure_pipeline.uniform_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&velocity_a.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&divergence.view),
+                    binding: 8,
+                    resource: brush_pipeline.stroke_buffer.as_entire_binding(),
                 },
             ],
         });
 
+        // Divergence Bind Groups (Read Vel -> Write Div)
+        let create_div = |vel_in: &Texture| -> BindGroup {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Divergence BG"),
+                layout: &pressure_pipeline.div_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: pressure_pipeline.uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&vel_in.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&divergence.view),
+                    },
+                ],
+            })
+        };
+        let div_bind_group = create_div(&velocity_a);
+        // Measures what the projection left behind. Divergence is recomputed at the start of the
+        // next projection anyway, so overwriting it here is free.
+        let check_div_bind_group = create_div(&velocity_b);
+
         // Jacobi Bind Groups (Ping Pong)
         let create_jacobi = |in_p: &Texture, out_p: &Texture| -> BindGroup {
             device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -189,24 +311,31 @@ impl FluidSim {
             ],
         });
 
-        let diffuse_pipeline = DiffusePipeline::new(device, width, height);
+        let diffuse_pipeline =
+            DiffusePipeline::new(device, width, height, DiffuseFormat::Rgba32Float);
+        let viscosity_pipeline =
+            DiffusePipeline::new(device, width, height, DiffuseFormat::Rg32Float);
 
         // UPDATE: Bind Groups for Diffusion
-        let create_diffuse_bg = |x_in: &Texture, b_in: &Texture, x_out: &Texture| -> BindGroup {
+        let create_diffuse_bg = |pipeline: &DiffusePipeline,
+                                 x_in: &Texture,
+                                 b_in: &Texture,
+                                 x_out: &Texture|
+         -> BindGroup {
             device.create_bind_group(&wgpu::BindGroupDescriptor {
                 label: Some("Diffuse BG"),
-                layout: &diffuse_pipeline.bind_group_layout,
+                layout: &pipeline.bind_group_layout,
                 entries: &[
                     wgpu::BindGroupEntry {
                         binding: 0,
-                        resource: diffuse_pipeline.uniform_buffer.as_entire_binding(),
+                        resource: pipeline.uniform_buffer.as_entire_binding(),
                     },
                     wgpu::BindGroupEntry {
                         binding: 1,
                         resource: wgpu::BindingResource::TextureView(&x_in.view),
                     },
                     // KEY FIX: This binding (2) is 'b_in' (Source).
-                    // We will now always pass the 'temp_density' here.
+                    // We will now always pass the temp snapshot here.
                     wgpu::BindGroupEntry {
                         binding: 2,
                         resource: wgpu::BindingResource::TextureView(&b_in.view),
@@ -221,10 +350,136 @@ impl FluidSim {
 
         let diffuse_bind_groups = vec![
             // 0: Read A, Source = TEMP, Write B
-            create_diffuse_bg(&density_a, &temp_density, &density_b),
+            create_diffuse_bg(&diffuse_pipeline, &density_a, &temp_density, &density_b),
             // 1: Read B, Source = TEMP, Write A
-            create_diffuse_bg(&density_b, &temp_density, &density_a),
+            create_diffuse_bg(&diffuse_pipeline, &density_b, &temp_density, &density_a),
         ];
+        let diffuse2_bind_groups = vec![
+            create_diffuse_bg(&diffuse_pipeline, &density2_a, &temp_density2, &density2_b),
+            create_diffuse_bg(&diffuse_pipeline, &density2_b, &temp_density2, &density2_a),
+        ];
+        let viscosity_bind_groups = vec![
+            create_diffuse_bg(
+                &viscosity_pipeline,
+                &velocity_a,
+                &temp_velocity,
+                &velocity_b,
+            ),
+            create_diffuse_bg(
+                &viscosity_pipeline,
+                &velocity_b,
+                &temp_velocity,
+                &velocity_a,
+            ),
+        ];
+
+        // SETTLING: the new paper goes to the temp texture (free at this point) and is copied back
+        let paper_pipeline = PaperPipeline::new(device);
+        let create_paper_bg = |density_in: &Texture,
+                               paper_in: &Texture,
+                               density_out: &Texture,
+                               paper_out: &Texture| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Paper BG"),
+                layout: &paper_pipeline.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: paper_pipeline.uniform_buffer.as_entire_binding(),
+                    },
+                    texture_entry(1, density_in),
+                    texture_entry(2, paper_in),
+                    texture_entry(3, &wetness_a),
+                    texture_entry(4, density_out),
+                    texture_entry(5, paper_out),
+                    texture_entry(6, &wetness_b),
+                    texture_entry(7, &grain),
+                ],
+            })
+        };
+        let paper_bind_group = create_paper_bg(&density_a, &paper, &density_b, &temp_density);
+        let paper2_bind_group = create_paper_bg(&density2_a, &paper2, &density2_b, &temp_density2);
+
+        let obstacle_brush_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Obstacle Brush BG"),
+            layout: &brush_pipeline.obstacle_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: brush_pipeline.brush_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&obstacles.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: brush_pipeline.stroke_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let multigrid = MultigridSolver::new(
+            device,
+            width,
+            height,
+            &pressure_a,
+            &pressure_b,
+            &divergence,
+            &obstacles,
+            &obstacle_layout,
+        );
+        // VORTICITY CONFINEMENT
+        let curl = Texture::create_storage_texture(
+            device,
+            width,
+            height,
+            wgpu::TextureFormat::R32Float,
+            Some("Curl"),
+        );
+        let vorticity_pipeline = VorticityPipeline::new(device);
+        let curl_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Curl BG"),
+            layout: &vorticity_pipeline.curl_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: vorticity_pipeline.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&velocity_a.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&curl.view),
+                },
+            ],
+        });
+        let confine_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Confinement BG"),
+            layout: &vorticity_pipeline.confine_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: vorticity_pipeline.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&velocity_a.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&curl.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&velocity_b.view),
+                },
+            ],
+        });
+
+        let divergence_monitor = DivergenceMonitor::new(device, &divergence, width, height);
 
         Self {
             width,
@@ -237,80 +492,471 @@ impl FluidSim {
             pressure_a,
             pressure_b,
             div_bind_group,
+            check_div_bind_group,
             jacobi_bind_groups,
             sub_bind_group,
             brush_pipeline,
             advect_pipeline,
             pressure_pipeline,
             advect_bind_group,
+            maccormack_bind_group,
+            advect2_bind_group,
+            maccormack2_bind_group,
             brush_bind_group,
+            obstacle_brush_bind_group,
+            obstacle_bind_group,
+            obstacles,
             temp_density,
+            temp_velocity,
+            density2_a,
+            density2_b,
+            temp_density2,
             diffuse_bind_groups,
+            diffuse2_bind_groups,
+            paper,
+            paper2,
+            wetness_a,
+            wetness_b,
+            grain,
+            paper_pipeline,
+            paper_bind_group,
+            paper2_bind_group,
             diffuse_pipeline,
+            viscosity_bind_groups,
+            viscosity_pipeline,
+            multigrid,
+            curl,
+            vorticity_pipeline,
+            curl_bind_group,
+            confine_bind_group,
+            divergence_monitor,
+        }
+    }
+
+    // One full simulation step of 'dt' simulated seconds. Shared by the windowed Canvas and the
+    // headless runner. 'brush' is the stroke in GRID coordinates (the points the cursor passed
+    // through, oldest first, see stroke.rs), or None when not painting.
+    // Uniforms are written with queue.write_buffer, which lands before the whole encoder runs:
+    // several steps recorded into one encoder must share dt and params, and only one of them
+    // may carry a stroke.
+    pub fn step(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        params: &GuiParams,
+        brush: Option<&[[f32; 2]]>,
+        clear: bool,
+        dt: f32,
+    ) {
+        if clear {
+            self.clear(encoder);
+        }
+
+        // 1. Diffuse (Spread Ink, then resist shear)
+        self.diffuse(queue, encoder, params, dt);
+        self.diffuse_velocity(queue, encoder, params, dt);
+
+        // 2. Advect (A -> B)
+        self.advect(queue, encoder, params, dt);
+
+        // 3. Brush (B -> A)
+        // If mouse is pressed, we run the brush shader which reads B and writes A.
+        // The wall tools only touch the obstacle mask, so the fields take the copy below.
+        let paints_fluid = !params.tool.edits_walls();
+        if let Some(stroke) = brush {
+            self.add_forces(queue, encoder, stroke, params);
+        }
+        if brush.is_none() || !paints_fluid {
+            // CRITICAL: If mouse is NOT pressed, we still need to get data from B back to A!
+            // Otherwise A stays stale.
+            encoder.copy_texture_to_texture(
+                self.density_b.texture.as_image_copy(),
+                self.density_a.texture.as_image_copy(),
+                self.density_a.texture.size(),
+            );
+            encoder.copy_texture_to_texture(
+                self.velocity_b.texture.as_image_copy(),
+                self.velocity_a.texture.as_image_copy(),
+                self.velocity_a.texture.size(),
+            );
+            if params.color_model == ColorModel::Palette {
+                encoder.copy_texture_to_texture(
+                    self.density2_b.texture.as_image_copy(),
+                    self.density2_a.texture.as_image_copy(),
+                    self.density2_a.texture.size(),
+                );
+            }
+        }
+
+        // 4. Settle (Some of the ink leaves the liquid for the paper, the paper dries)
+        self.settle(queue, encoder, params, dt);
+
+        // 5. Vorticity Confinement (Put back the small swirls advection smeared out)
+        self.confine_vorticity(queue, encoder, params, dt);
+
+        // 6. Project (Clean up Velocity A -> B -> A)
+        // This makes the liquid swirly!
+        self.project(queue, encoder, params, dt);
+    }
+
+    // What an undo snapshot holds (history.rs): the painting, its motion, the walls and how wet
+    // the paper is. The second pigment layers only matter in palette mode.
+    pub fn history_layers(&self, palette: bool) -> Vec<&Texture> {
+        let mut layers = vec![
+            &self.density_a,
+            &self.velocity_a,
+            &self.paper,
+            &self.wetness_a,
+            &self.obstacles,
+        ];
+        if palette {
+            layers.extend([&self.density2_a, &self.paper2]);
+        }
+        layers
+    }
+
+    // Every texture a project file (io_mod/project.rs) saves, under its name in the file.
+    // The rest are scratch space that each step overwrites. Names must never change.
+    pub fn project_fields(&self) -> [(&'static str, &Texture); 9] {
+        [
+            ("density", &self.density_a),
+            ("velocity", &self.velocity_a),
+            ("pressure", &self.pressure_a), // The solvers' warm start
+            ("density2", &self.density2_a),
+            ("paper", &self.paper),
+            ("paper2", &self.paper2),
+            ("wetness", &self.wetness_a),
+            ("obstacles", &self.obstacles),
+            ("grain", &self.grain),
+        ]
+    }
+
+    // CPU READBACK: Blocks until the GPU has finished all submitted work.
+    // Returns one [r, g, b, a] per cell, row-major, top row first.
+    pub fn read_density(&self, device: &Device, queue: &Queue) -> Vec<[f32; 4]> {
+        bytemuck::cast_slice(&self.density_a.read_back(device, queue)).to_vec()
+    }
+
+    // Palette mode: the concentrations of pigments 4-7, same layout as read_density
+    pub fn read_density2(&self, device: &Device, queue: &Queue) -> Vec<[f32; 4]> {
+        bytemuck::cast_slice(&self.density2_a.read_back(device, queue)).to_vec()
+    }
+
+    // The settled ink, same layout as read_density / read_density2
+    pub fn read_paper(&self, device: &Device, queue: &Queue) -> Vec<[f32; 4]> {
+        bytemuck::cast_slice(&self.paper.read_back(device, queue)).to_vec()
+    }
+
+    pub fn read_paper2(&self, device: &Device, queue: &Queue) -> Vec<[f32; 4]> {
+        bytemuck::cast_slice(&self.paper2.read_back(device, queue)).to_vec()
+    }
+
+    // One relief value per cell (-1 valley .. +1 ridge), row-major, top row first.
+    pub fn read_grain(&self, device: &Device, queue: &Queue) -> Vec<f32> {
+        self.grain.read_back(device, queue)
+    }
+
+    // Returns one [vx, vy] per cell, row-major, top row first.
+    pub fn read_velocity(&self, device: &Device, queue: &Queue) -> Vec<[f32; 2]> {
+        bytemuck::cast_slice(&self.velocity_a.read_back(device, queue)).to_vec()
+    }
+
+    // Returns one value per cell, row-major, top row first.
+    pub fn read_pressure(&self, device: &Device, queue: &Queue) -> Vec<f32> {
+        self.pressure_a.read_back(device, queue)
+    }
+
+    // Returns one value per cell (1.0 = solid), row-major, top row first.
+    pub fn read_obstacles(&self, device: &Device, queue: &Queue) -> Vec<f32> {
+        self.obstacles.read_back(device, queue)
+    }
+
+    // Builds a fresh sim of a new size and records passes that resample the current ink,
+    // velocity, second pigment field, paper, grain and obstacles into it. Pressure is not carried over;
+    // the next projection rebuilds it, and the new paper starts dry.
+    pub fn resized(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        width: u32,
+        height: u32,
+    ) -> FluidSim {
+        let new_sim = FluidSim::new(device, width, height);
+
+        // Velocity is in cells/second, so it scales with the grid to keep the same on-canvas speed
+        let velocity_scale = [
+            width as f32 / self.width as f32,
+            height as f32 / self.height as f32,
+        ];
+        let resample = ResamplePipeline::new(device, velocity_scale);
+
+        let create_bg = |layout: &wgpu::BindGroupLayout, src: &Texture, dst: &Texture| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Resample BG"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: resample.uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&src.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&src.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&dst.view),
+                    },
+                ],
+            })
+        };
+        let density_bg = create_bg(
+            &resample.density_layout,
+            &self.density_a,
+            &new_sim.density_a,
+        );
+        let velocity_bg = create_bg(
+            &resample.velocity_layout,
+            &self.velocity_a,
+            &new_sim.velocity_a,
+        );
+        let density2_bg = create_bg(
+            &resample.density_layout,
+            &self.density2_a,
+            &new_sim.density2_a,
+        );
+        let paper_bg = create_bg(&resample.density_layout, &self.paper, &new_sim.paper);
+        let paper2_bg = create_bg(&resample.density_layout, &self.paper2, &new_sim.paper2);
+        let obstacle_bg = create_bg(&resample.mask_layout, &self.obstacles, &new_sim.obstacles);
+        let grain_bg = create_bg(&resample.mask_layout, &self.grain, &new_sim.grain);
+
+        let x_groups = (width as f32 / 16.0).ceil() as u32;
+        let y_groups = (height as f32 / 16.0).ceil() as u32;
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Resample Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&resample.density_pipeline);
+        pass.set_bind_group(0, &density_bg, &[]);
+        pass.dispatch_workgroups(x_groups, y_groups, 1);
+        pass.set_bind_group(0, &density2_bg, &[]);
+        pass.dispatch_workgroups(x_groups, y_groups, 1);
+        pass.set_bind_group(0, &paper_bg, &[]);
+        pass.dispatch_workgroups(x_groups, y_groups, 1);
+        pass.set_bind_group(0, &paper2_bg, &[]);
+        pass.dispatch_workgroups(x_groups, y_groups, 1);
+        pass.set_pipeline(&resample.velocity_pipeline);
+        pass.set_bind_group(0, &velocity_bg, &[]);
+        pass.dispatch_workgroups(x_groups, y_groups, 1);
+        pass.set_pipeline(&resample.mask_pipeline);
+        pass.set_bind_group(0, &obstacle_bg, &[]);
+        pass.dispatch_workgroups(x_groups, y_groups, 1);
+        pass.set_pipeline(&resample.scalar_pipeline);
+        pass.set_bind_group(0, &grain_bg, &[]);
+        pass.dispatch_workgroups(x_groups, y_groups, 1);
+        drop(pass);
+
+        new_sim
+    }
+
+    // CPU UPLOAD: Replaces the current ink field. Expects width * height cells.
+    pub fn upload_density(&self, queue: &Queue, density: &[[f32; 4]]) {
+        self.density_a
+            .write_all(queue, bytemuck::cast_slice(density));
+    }
+
+    pub fn upload_density2(&self, queue: &Queue, density: &[[f32; 4]]) {
+        self.density2_a
+            .write_all(queue, bytemuck::cast_slice(density));
+    }
+
+    pub fn upload_paper(&self, queue: &Queue, paper: &[[f32; 4]]) {
+        self.paper.write_all(queue, bytemuck::cast_slice(paper));
+    }
+
+    pub fn upload_paper2(&self, queue: &Queue, paper: &[[f32; 4]]) {
+        self.paper2.write_all(queue, bytemuck::cast_slice(paper));
+    }
+
+    // One relief value per cell, see grain.rs
+    pub fn upload_grain(&self, queue: &Queue, relief: &[f32]) {
+        self.grain.write_all(queue, bytemuck::cast_slice(relief));
+    }
+
+    pub fn upload_velocity(&self, queue: &Queue, velocity: &[[f32; 2]]) {
+        self.velocity_a
+            .write_all(queue, bytemuck::cast_slice(velocity));
+    }
+
+    // The right-hand side of the pressure solve, one value per cell
+    pub fn upload_divergence(&self, queue: &Queue, divergence: &[f32]) {
+        self.divergence
+            .write_all(queue, bytemuck::cast_slice(divergence));
+    }
+
+    // One value per cell, 1.0 = solid
+    pub fn upload_obstacles(&self, queue: &Queue, obstacles: &[f32]) {
+        self.obstacles
+            .write_all(queue, bytemuck::cast_slice(obstacles));
+    }
+
+    // Ink bleed: spreads the ink through the paper, independent of how the liquid moves
+    pub fn diffuse(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        params: &GuiParams,
+        dt: f32,
+    ) {
+        Self::solve_diffusion(
+            queue,
+            encoder,
+            params,
+            params.ink_bleed * dt,
+            &self.diffuse_pipeline,
+            &self.diffuse_bind_groups,
+            [&self.density_a, &self.density_b, &self.temp_density],
+        );
+        // Every pigment bleeds at the same rate. Same uniforms, so the shared buffer is fine.
+        if params.color_model == ColorModel::Palette {
+            Self::solve_diffusion(
+                queue,
+                encoder,
+                params,
+                params.ink_bleed * dt,
+                &self.diffuse_pipeline,
+                &self.diffuse2_bind_groups,
+                [&self.density2_a, &self.density2_b, &self.temp_density2],
+            );
         }
     }
 
-    pub fn diffuse(&self, queue: &Queue, encoder: &mut CommandEncoder, params: &GuiParams) {
-        if params.viscosity <= 0.0 {
+    // Viscosity: diffuses velocity, so neighbouring paint resists moving at different speeds
+    pub fn diffuse_velocity(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        params: &GuiParams,
+        dt: f32,
+    ) {
+        Self::solve_diffusion(
+            queue,
+            encoder,
+            params,
+            params.viscosity * dt,
+            &self.viscosity_pipeline,
+            &self.viscosity_bind_groups,
+            [&self.velocity_a, &self.velocity_b, &self.temp_velocity],
+        );
+    }
+
+    // Implicit diffusion of one field, in place in A. 'fields' is [A, B, Temp].
+    // 'amount' is the diffusion rate times dt for this step.
+    fn solve_diffusion(
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        params: &GuiParams,
+        amount: f32,
+        pipeline: &DiffusePipeline,
+        bind_groups: &[BindGroup],
+        [field_a, field_b, temp]: [&Texture; 3],
+    ) {
+        if amount <= 0.0 {
             return;
         } // No diffusion needed
 
         // --- THE MATH ---
         // Formula: x = (neighbors + alpha * original) / beta
-        // alpha = 1 / (viscosity * dt)
+        // alpha = 1 / (rate * dt) = 1 / amount
         // beta = 4 + alpha
 
-        // 1. SAFEGUARD: Copy Density A (Source) to Temp (b_in)
-        // This snapshots the density so we can read it stably while writing new values.
+        // 1. SAFEGUARD: Copy A (Source) to Temp (b_in)
+        // This snapshots the field so we can read it stably while writing new values.
         encoder.copy_texture_to_texture(
-            self.density_a.texture.as_image_copy(),
-            self.temp_density.texture.as_image_copy(),
-            self.density_a.texture.size(),
+            field_a.texture.as_image_copy(),
+            temp.texture.as_image_copy(),
+            field_a.texture.size(),
         );
 
-        let dt = 0.016;
         let dx = 1.0; // Pixel size
 
-        // High Viscosity = Small Alpha (Neighbors dominate)
-        // Low Viscosity  = Large Alpha (Original dominates)
-        let alpha = (dx * dx) / (params.viscosity * dt);
+        // High Rate = Small Alpha (Neighbors dominate)
+        // Low Rate  = Large Alpha (Original dominates)
+        let alpha = (dx * dx) / amount;
         let beta = 4.0 + alpha;
 
         // Upload to GPU
+        let (width, height) = (field_a.texture.width(), field_a.texture.height());
         let uniforms = DiffuseUniforms {
-            width: self.width as f32,
-            height: self.height as f32,
+            width: width as f32,
+            height: height as f32,
             alpha,
             one_over_beta: 1.0 / beta, // Optimization: Multiply instead of divide
+            omega: params.sor_omega,
+            _padding: [0.0; 3],
+            boundary: params.boundary_modes(),
         };
         queue.write_buffer(
-            &self.diffuse_pipeline.uniform_buffer,
+            &pipeline.uniform_buffer,
             0,
             bytemuck::cast_slice(&[uniforms]),
         );
 
-        // --- THE SOLVER (Jacobi Iteration) ---
-        let x_groups = (self.width as f32 / 16.0).ceil() as u32;
-        let y_groups = (self.height as f32 / 16.0).ceil() as u32;
+        // --- THE SOLVER ---
+        let x_groups = (width as f32 / 16.0).ceil() as u32;
+        let y_groups = (height as f32 / 16.0).ceil() as u32;
+
+        // Every pass ping-pongs: even passes write B, odd passes write A
+        let n = params.diffuse_iterations as usize;
+        let passes: Vec<(&ComputePipeline, &BindGroup)> = match params.diffuse_solver {
+            DiffuseSolver::Jacobi => (0..n)
+                .map(|i| (&pipeline.pipeline, &bind_groups[i % 2]))
+                .collect(),
+            // One iteration = red sweep (A -> B) + black sweep (B -> A)
+            DiffuseSolver::RedBlackSor => (0..n)
+                .flat_map(|_| {
+                    [
+                        (&pipeline.rb_red_pipeline, &bind_groups[0]),
+                        (&pipeline.rb_black_pipeline, &bind_groups[1]),
+                    ]
+                })
+                .collect(),
+        };
 
-        // 20 Iterations is usually enough for visual diffusion
-        for i in 0..50 {
-            let idx = i % 2;
+        for (pipeline, bind_group) in &passes {
             let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label: Some("Diffuse Pass"),
                 timestamp_writes: None,
             });
-            pass.set_pipeline(&self.diffuse_pipeline.pipeline);
-            pass.set_bind_group(0, &self.diffuse_bind_groups[idx], &[]);
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, *bind_group, &[]);
             pass.dispatch_workgroups(x_groups, y_groups, 1);
         }
 
-        // Ensure Density A has the final result (if we ended on B, copy B->A)
-        // Since we run 20 iterations (even number), we end writing to A.
-        // So no copy needed! (0->1, 1->0, ... 19->0).
+        // Ensure A has the final result (if we ended on B, copy B->A)
+        if passes.len() % 2 == 1 {
+            encoder.copy_texture_to_texture(
+                field_b.texture.as_image_copy(),
+                field_a.texture.as_image_copy(),
+                field_a.texture.size(),
+            );
+        }
     }
 
-    pub fn project(&mut self, encoder: &mut CommandEncoder) {
+    pub fn project(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        params: &GuiParams,
+        dt: f32,
+    ) {
+        self.write_pressure_uniforms(queue, params, dt);
+
         let x_groups = (self.width as f32 / 16.0).ceil() as u32;
         let y_groups = (self.height as f32 / 16.0).ceil() as u32;
 
@@ -322,21 +968,12 @@ impl FluidSim {
             });
             pass.set_pipeline(&self.pressure_pipeline.div_pipeline);
             pass.set_bind_group(0, &self.div_bind_group, &[]);
+            pass.set_bind_group(1, &self.obstacle_bind_group, &[]);
             pass.dispatch_workgroups(x_groups, y_groups, 1);
         }
 
-        // 2. Solve Pressure (Jacobi Iteration)
-        // Run this 20-50 times to propagate pressure across the grid
-        for i in 0..40 {
-            let in_index = i % 2;
-            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Jacobi Pass"),
-                timestamp_writes: None,
-            });
-            pass.set_pipeline(&self.pressure_pipeline.jacobi_pipeline);
-            pass.set_bind_group(0, &self.jacobi_bind_groups[in_index], &[]);
-            pass.dispatch_workgroups(x_groups, y_groups, 1);
-        }
+        // 2. Solve Pressure
+        let work = self.record_pressure_solve(queue, encoder, params);
 
         // 3. Subtract Gradient
         // Uses final pressure (A) and current velocity (A) to write new velocity (B)
@@ -347,10 +984,26 @@ impl FluidSim {
             });
             pass.set_pipeline(&self.pressure_pipeline.sub_pipeline);
             pass.set_bind_group(0, &self.sub_bind_group, &[]);
+            pass.set_bind_group(1, &self.obstacle_bind_group, &[]);
             pass.dispatch_workgroups(x_groups, y_groups, 1);
         }
 
-        // 4. Enforce Invariant: A is always valid
+        // 4. Measure the leftover divergence (only when the previous reading has come back)
+        if self.divergence_monitor.is_idle() {
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Divergence Check Pass"),
+                    timestamp_writes: None,
+                });
+                pass.set_pipeline(&self.pressure_pipeline.div_pipeline);
+                pass.set_bind_group(0, &self.check_div_bind_group, &[]);
+                pass.set_bind_group(1, &self.obstacle_bind_group, &[]);
+                pass.dispatch_workgroups(x_groups, y_groups, 1);
+            }
+            self.divergence_monitor.record(encoder, work);
+        }
+
+        // 5. Enforce Invariant: A is always valid
         // Copy B (Result) -> A
         encoder.copy_texture_to_texture(
             self.velocity_b.texture.as_image_copy(),
@@ -359,17 +1012,129 @@ impl FluidSim {
         );
     }
 
+    // The uniforms shared by the divergence, solver and subtract passes
+    fn write_pressure_uniforms(&self, queue: &Queue, params: &GuiParams, dt: f32) {
+        // The shaders solve for pressure already scaled by dt / density, so the projection
+        // itself doesn't depend on dt. It is passed along for completeness.
+        let uniforms = PressureUniforms {
+            width: self.width as f32,
+            height: self.height as f32,
+            dt,
+            omega: params.sor_omega,
+            boundary: params.boundary_modes(),
+        };
+        queue.write_buffer(
+            &self.pressure_pipeline.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[uniforms]),
+        );
+    }
+
+    // Solves into pressure A from the divergence field, warm-started from A, with the selected
+    // solver. Returns the iterations (or V-cycles) it ran.
+    fn record_pressure_solve(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        params: &GuiParams,
+    ) -> u32 {
+        // Iterations for Jacobi / SOR, V-cycles for multigrid. The slider value is the cap.
+        let limit = match params.pressure_solver {
+            PressureSolver::Multigrid => params.multigrid_cycles,
+            _ => params.pressure_iterations,
+        };
+        let work = if params.adaptive_pressure {
+            self.divergence_monitor
+                .adaptive_budget(limit, params.divergence_tolerance)
+        } else {
+            limit
+        };
+
+        let x_groups = (self.width as f32 / 16.0).ceil() as u32;
+        let y_groups = (self.height as f32 / 16.0).ceil() as u32;
+        let pipeline = &self.pressure_pipeline;
+        let n = work as usize;
+        // Every pass ping-pongs: even passes write B, odd passes write A
+        let passes: Vec<(&ComputePipeline, &BindGroup)> = match params.pressure_solver {
+            PressureSolver::Jacobi => (0..n)
+                .map(|i| (&pipeline.jacobi_pipeline, &self.jacobi_bind_groups[i % 2]))
+                .collect(),
+            // One iteration = red sweep (A -> B) + black sweep (B -> A)
+            PressureSolver::RedBlackSor => (0..n)
+                .flat_map(|_| {
+                    [
+                        (&pipeline.rb_red_pipeline, &self.jacobi_bind_groups[0]),
+                        (&pipeline.rb_black_pipeline, &self.jacobi_bind_groups[1]),
+                    ]
+                })
+                .collect(),
+            // V-cycles converge on the whole grid at once, regardless of its size
+            PressureSolver::Multigrid => {
+                self.multigrid.set_boundary(queue, params.boundary_modes());
+                self.multigrid.solve(encoder, work);
+                Vec::new()
+            }
+        };
+
+        for (pipeline, bind_group) in &passes {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Pressure Solve Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, *bind_group, &[]);
+            pass.set_bind_group(1, &self.obstacle_bind_group, &[]);
+            pass.dispatch_workgroups(x_groups, y_groups, 1);
+        }
+
+        // Subtract reads Pressure A: if we ended on B, copy B->A
+        if passes.len() % 2 == 1 {
+            encoder.copy_texture_to_texture(
+                self.pressure_b.texture.as_image_copy(),
+                self.pressure_a.texture.as_image_copy(),
+                self.pressure_a.texture.size(),
+            );
+        }
+        work
+    }
+
+    // Only the pressure solve, on the divergence field as it is (see upload_divergence).
+    // Lets the GPU solvers be checked against poisson_reference.rs.
+    pub fn solve_pressure(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        params: &GuiParams,
+    ) {
+        self.write_pressure_uniforms(queue, params, 0.0);
+        self.record_pressure_solve(queue, encoder, params);
+    }
+
+    // Call after submitting the encoder passed to step(). Never blocks.
+    pub fn after_submit(&mut self, device: &Device) {
+        self.divergence_monitor.after_submit();
+        self.divergence_monitor.poll(device);
+    }
+
+    // The most recent divergence measurement, usually a frame or two old
+    pub fn divergence_stats(&self) -> Option<DivergenceStats> {
+        self.divergence_monitor.latest()
+    }
+
     // Update signature to accept Queue and Params
-    pub fn advect(&self, queue: &Queue, encoder: &mut CommandEncoder, params: &GuiParams) {
+    pub fn advect(&self, queue: &Queue, encoder: &mut CommandEncoder, params: &GuiParams, dt: f32) {
         // 1. Create the new Uniform data from the UI params
+        // The decay sliders are "per 1/60 s": rescale them so a different dt loses the same amount
         let uniforms = AdvectionUniforms {
-            dt: 0.016,
+            dt,
             width: self.width as f32,
             height: self.height as f32,
             // Connect UI Sliders to Physics
-            velocity_decay: params.velocity_decay,
-            ink_decay: params.ink_decay,
-            _padding: [0.0; 3],
+            velocity_decay: params.velocity_decay.powf(dt / SIM_DT),
+            ink_decay: params.ink_decay.powf(dt / SIM_DT),
+            grain_friction: params.grain_friction,
+            _padding: [0.0; 2],
+            boundary: params.boundary_modes(),
         };
 
         // 2. Upload it to the GPU
@@ -379,57 +1144,266 @@ impl FluidSim {
             bytemuck::cast_slice(&[uniforms]),
         );
 
-        // 3. Dispatch (Same as before)
-        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-            label: Some("Advection Pass"),
-            timestamp_writes: None,
-        });
-        compute_pass.set_pipeline(&self.advect_pipeline.pipeline);
-        compute_pass.set_bind_group(0, &self.advect_bind_group, &[]);
+        let x_groups = (self.width as f32 / 16.0).ceil() as u32;
+        let y_groups = (self.height as f32 / 16.0).ceil() as u32;
+
+        // 3. Dispatch
+        let pipeline = &self.advect_pipeline;
+        let mut passes = match params.advection {
+            AdvectionScheme::SemiLagrangian => {
+                vec![(&pipeline.pipeline, &self.advect_bind_group)]
+            }
+            // Forward step (A -> B), then the limited correction (A + B -> Temp)
+            AdvectionScheme::MacCormack => vec![
+                (&pipeline.predict_pipeline, &self.advect_bind_group),
+                (&pipeline.maccormack_pipeline, &self.maccormack_bind_group),
+            ],
+        };
+        // Palette mode: pigments 4-7 follow the same (old) velocity, written after the first field
+        let palette = params.color_model == ColorModel::Palette;
+        if palette {
+            passes.extend(match params.advection {
+                AdvectionScheme::SemiLagrangian => {
+                    vec![(&pipeline.ink_pipeline, &self.advect2_bind_group)]
+                }
+                AdvectionScheme::MacCormack => vec![
+                    (&pipeline.ink_predict_pipeline, &self.advect2_bind_group),
+                    (
+                        &pipeline.ink_maccormack_pipeline,
+                        &self.maccormack2_bind_group,
+                    ),
+                ],
+            });
+        }
+        for (pipeline, bind_group) in &passes {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Advection Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, *bind_group, &[]);
+            compute_pass.set_bind_group(1, &self.obstacle_bind_group, &[]);
+            compute_pass.dispatch_workgroups(x_groups, y_groups, 1);
+        }
+
+        // The brush reads B: move the corrected result there
+        if params.advection == AdvectionScheme::MacCormack {
+            encoder.copy_texture_to_texture(
+                self.temp_density.texture.as_image_copy(),
+                self.density_b.texture.as_image_copy(),
+                self.density_b.texture.size(),
+            );
+            encoder.copy_texture_to_texture(
+                self.temp_velocity.texture.as_image_copy(),
+                self.velocity_b.texture.as_image_copy(),
+                self.velocity_b.texture.size(),
+            );
+            if palette {
+                encoder.copy_texture_to_texture(
+                    self.temp_density2.texture.as_image_copy(),
+                    self.density2_b.texture.as_image_copy(),
+                    self.density2_b.texture.size(),
+                );
+            }
+        }
+    }
+
+    // Moves settled ink from the liquid (A) into the paper and dries the paper.
+    // Wet paper holds the ink in the liquid; dry paper takes it quickly.
+    // The paper dries even with settling off, so turning it on later doesn't find old strokes wet.
+    pub fn settle(&self, queue: &Queue, encoder: &mut CommandEncoder, params: &GuiParams, dt: f32) {
+        let depositing = params.deposit_rate > 0.0;
+
+        let uniforms = PaperUniforms {
+            dt,
+            deposit_rate: params.deposit_rate,
+            drying_time: params.drying_time,
+            color_model: params.color_model as u32,
+            granulation: params.granulation,
+            absorption: params.grain_absorption,
+            edge_darkening: params.edge_darkening,
+            _padding: 0.0,
+        };
+        queue.write_buffer(
+            &self.paper_pipeline.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[uniforms]),
+        );
 
         let x_groups = (self.width as f32 / 16.0).ceil() as u32;
         let y_groups = (self.height as f32 / 16.0).ceil() as u32;
-        compute_pass.dispatch_workgroups(x_groups, y_groups, 1);
+
+        // Palette mode: pigments 4-7 settle the same way (and write the same wetness)
+        let palette = params.color_model == ColorModel::Palette && depositing;
+        let mut bind_groups = vec![&self.paper_bind_group];
+        if palette {
+            bind_groups.push(&self.paper2_bind_group);
+        }
+        for bind_group in bind_groups {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Settle Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.paper_pipeline.pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(x_groups, y_groups, 1);
+        }
+
+        // Enforce Invariant: A is always valid.
+        // Without settling the ink and paper come out unchanged, only the wetness is new.
+        let mut copies = vec![(&self.wetness_b, &self.wetness_a)];
+        if depositing {
+            copies.push((&self.density_b, &self.density_a));
+            copies.push((&self.temp_density, &self.paper));
+        }
+        if palette {
+            copies.push((&self.density2_b, &self.density2_a));
+            copies.push((&self.temp_density2, &self.paper2));
+        }
+        for (src, dst) in copies {
+            encoder.copy_texture_to_texture(
+                src.texture.as_image_copy(),
+                dst.texture.as_image_copy(),
+                dst.texture.size(),
+            );
+        }
+    }
+
+    pub fn confine_vorticity(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        params: &GuiParams,
+        dt: f32,
+    ) {
+        if params.vorticity <= 0.0 {
+            return;
+        }
+
+        let uniforms = VorticityUniforms {
+            strength: params.vorticity,
+            dt,
+            _padding: [0.0; 2],
+            boundary: params.boundary_modes(),
+        };
+        queue.write_buffer(
+            &self.vorticity_pipeline.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[uniforms]),
+        );
+
+        let x_groups = (self.width as f32 / 16.0).ceil() as u32;
+        let y_groups = (self.height as f32 / 16.0).ceil() as u32;
+
+        // 1. Curl (Vel A -> Curl)
+        // 2. Confinement force (Vel A + Curl -> Vel B)
+        let passes = [
+            (
+                &self.vorticity_pipeline.curl_pipeline,
+                &self.curl_bind_group,
+            ),
+            (
+                &self.vorticity_pipeline.confine_pipeline,
+                &self.confine_bind_group,
+            ),
+        ];
+        for (pipeline, bind_group) in passes {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Vorticity Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(x_groups, y_groups, 1);
+        }
+
+        // 3. Enforce Invariant: A is always valid
+        encoder.copy_texture_to_texture(
+            self.velocity_b.texture.as_image_copy(),
+            self.velocity_a.texture.as_image_copy(),
+            self.velocity_a.texture.size(),
+        );
     }
 
     pub fn add_forces(
         &mut self,
         queue: &Queue,
         encoder: &mut CommandEncoder,
-        current_pos: [f32; 2],
-        last_pos: [f32; 2],
+        stroke: &[[f32; 2]],
         params: &GuiParams,
     ) {
-        let smudge_val = if params.smudge { 1.0 } else { 0.0 };
+        let path = stroke_path(stroke);
+        if path.is_empty() {
+            return;
+        }
+
+        // Palette mode: full concentration of the selected pigment, none of the others.
+        // Params may come from outside the panel (the headless runner): stay on the palette.
+        let palette = params.color_model == ColorModel::Palette;
+        let pigment = params.pigment.min(PALETTE_SIZE - 1);
+        let mut targets = [[0.0; 4]; 2];
+        targets[pigment / 4][pigment % 4] = 1.0;
+
         // Update Uniforms
+        let radius = params.brush_size / params.view.zoom;
         let brush_data = BrushUniforms {
-            mouse_pos: current_pos,
-            last_mouse_pos: last_pos,
+            point_count: path.len() as u32,
+            path_length: path_length(&path),
             velocity_factor: params.velocity_factor,
-            radius: params.brush_size / params.zoom_level,
-            smudge: smudge_val,
-            _padding: [0.0; 1], // Zero out padding
-            brush_color: params.brush_color,
+            radius,
+            tool: params.tool as u32,
+            palette: u32::from(palette),
+            // In pigment mode the brush deposits K/S values; the blend itself is the same
+            brush_color: match params.color_model {
+                ColorModel::Rgb => params.brush_color,
+                ColorModel::Pigment => brush_pigment(params.brush_color),
+                ColorModel::Palette => targets[0],
+            },
+            brush_color2: targets[1],
+            amount: params.brush_color[3],
+            _padding: 0.0, // Zero out padding
+            bounds: stroke_bounds(&path, radius),
         };
         queue.write_buffer(
             &self.brush_pipeline.brush_buffer,
             0,
             bytemuck::cast_slice(&[brush_data]),
         );
+        queue.write_buffer(
+            &self.brush_pipeline.stroke_buffer,
+            0,
+            bytemuck::cast_slice(&path),
+        );
 
-        // Dispatch
+        // Dispatch: ink and velocity (B -> A), or the obstacle mask for the wall tools
+        let (pipeline, bind_group) = if params.tool.edits_walls() {
+            (
+                &self.brush_pipeline.obstacle_pipeline,
+                &self.obstacle_brush_bind_group,
+            )
+        } else {
+            (&self.brush_pipeline.pipeline, &self.brush_bind_group)
+        };
         let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("Brush Pass"),
             timestamp_writes: None,
         });
-        compute_pass.set_pipeline(&self.brush_pipeline.pipeline);
-        compute_pass.set_bind_group(0, &self.brush_bind_group, &[]);
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, bind_group, &[]);
 
         let x_groups = (self.width as f32 / 16.0).ceil() as u32;
         let y_groups = (self.height as f32 / 16.0).ceil() as u32;
         compute_pass.dispatch_workgroups(x_groups, y_groups, 1);
     }
 
+    // Removes every painted wall. Not part of clear(): the walls are a separate layer.
+    pub fn clear_obstacles(&self, encoder: &mut CommandEncoder) {
+        encoder.clear_texture(
+            &self.obstacles.texture,
+            &wgpu::ImageSubresourceRange::default(),
+        );
+    }
+
     pub fn clear(&self, encoder: &mut CommandEncoder) {
         let mut clear_tex = |tex: &Texture| {
             encoder.clear_texture(
@@ -447,6 +1421,12 @@ impl FluidSim {
         // Wipe everything
         clear_tex(&self.density_a);
         clear_tex(&self.density_b);
+        clear_tex(&self.density2_a);
+        clear_tex(&self.density2_b);
+        clear_tex(&self.paper);
+        clear_tex(&self.paper2);
+        clear_tex(&self.wetness_a);
+        clear_tex(&self.wetness_b);
         clear_tex(&self.velocity_a);
         clear_tex(&self.velocity_b);
         clear_tex(&self.pressure_a);
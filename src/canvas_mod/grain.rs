@@ -0,0 +1,52 @@
+// --- PAPER GRAIN ---
+// The paper's surface relief, one value per cell: 0 = flat, +1 = ridge, -1 = valley.
+// A fresh sim starts flat, which leaves every grain effect switched off.
+
+// Hash of a lattice point -> [0, 1)
+fn lattice(x: i32, y: i32, seed: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ seed.wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    (h & 0x00ff_ffff) as f32 / 16_777_216.0
+}
+
+// Smoothly interpolated value noise, [0, 1)
+fn value_noise(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+    let (ix, iy) = (x0 as i32, y0 as i32);
+    let top = lattice(ix, iy, seed) + (lattice(ix + 1, iy, seed) - lattice(ix, iy, seed)) * tx;
+    let bottom = lattice(ix, iy + 1, seed)
+        + (lattice(ix + 1, iy + 1, seed) - lattice(ix, iy + 1, seed)) * tx;
+    top + (bottom - top) * ty
+}
+
+// Cold-pressed looking paper: a few octaves of value noise, the finest ones strongest so the
+// tooth reads at brush scale. 'scale' is the size of the largest bumps in cells.
+pub fn generate_grain(width: u32, height: u32, scale: f32, seed: u32) -> Vec<f32> {
+    const OCTAVES: [(f32, f32); 4] = [(1.0, 0.2), (0.5, 0.3), (0.25, 0.3), (0.125, 0.2)];
+    let scale = scale.max(1.0);
+    let mut relief = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = 0.0;
+            for (octave, &(size, weight)) in OCTAVES.iter().enumerate() {
+                let cell = scale * size;
+                sum += weight * value_noise(x as f32 / cell, y as f32 / cell, seed + octave as u32);
+            }
+            relief.push((sum * 2.0 - 1.0).clamp(-1.0, 1.0));
+        }
+    }
+    relief
+}
+
+// Paper colour at a cell. Valleys sit in shadow; ridges and flat paper keep the base colour.
+// fs_main (shader.wgsl) and the PNG export shade the paper with this.
+pub fn paper_shade(paper_color: [f32; 3], relief: f32, shading: f32) -> [f32; 3] {
+    let shade = 1.0 + shading * 0.5 * relief.min(0.0);
+    paper_color.map(|c| c * shade)
+}
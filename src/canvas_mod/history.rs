@@ -0,0 +1,189 @@
+// --- UNDO HISTORY ---
+// Snapshots of the painting, kept on the GPU as plain texture copies. Taking one or going back
+// is only a few copy commands in the frame's encoder, with no readback.
+// A snapshot holds whatever layers FluidSim::history_layers lists, so it is only valid for the
+// grid size and colour model it was taken with: the owner clears the history when either changes.
+use super::resources::texture::Texture;
+use std::collections::VecDeque;
+use wgpu::{CommandEncoder, Device};
+
+pub struct Snapshot {
+    layers: Vec<wgpu::Texture>,
+    bytes: u64,
+}
+
+impl Snapshot {
+    // Records copies of 'sources' into new textures
+    fn capture(device: &Device, encoder: &mut CommandEncoder, sources: &[&Texture]) -> Self {
+        let mut bytes = 0;
+        let layers = sources
+            .iter()
+            .map(|source| {
+                let size = source.texture.size();
+                let format = source.texture.format();
+                bytes += u64::from(size.width)
+                    * u64::from(size.height)
+                    * u64::from(format.block_copy_size(None).unwrap_or(0));
+
+                let copy = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Undo Snapshot"),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+                    view_formats: &[],
+                });
+                encoder.copy_texture_to_texture(
+                    source.texture.as_image_copy(),
+                    copy.as_image_copy(),
+                    size,
+                );
+                copy
+            })
+            .collect();
+        Self { layers, bytes }
+    }
+
+    // Records copies of the snapshot back into the live textures
+    fn restore(&self, encoder: &mut CommandEncoder, targets: &[&Texture]) {
+        for (layer, target) in self.layers.iter().zip(targets) {
+            encoder.copy_texture_to_texture(
+                layer.as_image_copy(),
+                target.texture.as_image_copy(),
+                layer.size(),
+            );
+        }
+    }
+}
+
+// What the history keeps: a GPU snapshot in the app, anything with a size in the tests
+pub trait HistoryEntry {
+    fn bytes(&self) -> u64;
+}
+
+impl HistoryEntry for Snapshot {
+    fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+pub struct History<S = Snapshot> {
+    undo: VecDeque<S>, // Oldest first
+    redo: VecDeque<S>, // Most recently undone last
+}
+
+impl<S> Default for History<S> {
+    fn default() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: VecDeque::new(),
+        }
+    }
+}
+
+// The bookkeeping: which entries are kept, and which one to restore
+impl<S: HistoryEntry> History<S> {
+    // Adds the state from before a change. Anything that was undone can't be redone any more.
+    pub fn record(&mut self, entry: S, budget: u64) {
+        self.redo.clear();
+        self.undo.push_back(entry);
+        self.fit(budget);
+    }
+
+    // Takes the entry to go back to; 'current' (only called if there is one) becomes the next redo
+    pub fn step_back(&mut self, budget: u64, current: impl FnOnce() -> S) -> Option<S> {
+        let entry = self.undo.pop_back()?;
+        self.redo.push_back(current());
+        self.fit(budget);
+        Some(entry)
+    }
+
+    // Takes the entry to go forward to; 'current' becomes the next undo
+    pub fn step_forward(&mut self, budget: u64, current: impl FnOnce() -> S) -> Option<S> {
+        let entry = self.redo.pop_back()?;
+        self.undo.push_back(current());
+        self.fit(budget);
+        Some(entry)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    // (undo steps, redo steps) available
+    pub fn steps(&self) -> (usize, usize) {
+        (self.undo.len(), self.redo.len())
+    }
+
+    // Memory held by the entries
+    pub fn bytes(&self) -> u64 {
+        self.undo
+            .iter()
+            .chain(&self.redo)
+            .map(HistoryEntry::bytes)
+            .sum()
+    }
+
+    // Drops entries until the history uses at most 'budget' bytes: the oldest undo steps first,
+    // then the redo steps furthest away. One step each way is always kept.
+    fn fit(&mut self, budget: u64) {
+        while self.bytes() > budget {
+            if self.undo.len() > 1 {
+                self.undo.pop_front();
+            } else if self.redo.len() > 1 {
+                self.redo.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl History {
+    // Remembers the current state of 'layers' (call BEFORE the change that may be undone)
+    pub fn push(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        layers: &[&Texture],
+        budget: u64,
+    ) {
+        self.record(Snapshot::capture(device, encoder, layers), budget);
+    }
+
+    // Goes back one snapshot. The current state becomes the next redo. False if there is none.
+    pub fn undo(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        layers: &[&Texture],
+        budget: u64,
+    ) -> bool {
+        let Some(snapshot) = self.step_back(budget, || Snapshot::capture(device, encoder, layers))
+        else {
+            return false;
+        };
+        snapshot.restore(encoder, layers);
+        true
+    }
+
+    // Reverses the last undo. False if there is nothing to redo.
+    pub fn redo(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        layers: &[&Texture],
+        budget: u64,
+    ) -> bool {
+        let Some(snapshot) =
+            self.step_forward(budget, || Snapshot::capture(device, encoder, layers))
+        else {
+            return false;
+        };
+        snapshot.restore(encoder, layers);
+        true
+    }
+}
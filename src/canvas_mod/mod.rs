@@ -1,4 +1,12 @@
 pub mod canvas;
+pub mod divergence_monitor;
 pub mod fluid_sim;
+pub mod grain;
+pub mod history;
+pub mod multigrid;
+pub mod pigment;
 pub mod pipelines;
+pub mod poisson_reference;
 pub mod resources;
+pub mod stroke;
+pub mod view;
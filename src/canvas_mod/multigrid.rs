@@ -0,0 +1,317 @@
+use super::pipelines::multigrid_pipeline::{MultigridPipeline, MultigridUniforms};
+use super::poisson_reference::{VCycleSettings, coarse_size, level_count};
+use super::resources::texture::Texture;
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroup, CommandEncoder, ComputePipeline, Device, Queue};
+
+// One grid in the hierarchy. Level 0 is the simulation grid itself.
+struct Level {
+    width: u32,
+    height: u32,
+    uniforms: wgpu::Buffer,
+    mask_bind_group: BindGroup, // group(1): this level's obstacle mask
+    // Ping-pong pair. Between passes the current solution always lives in [0].
+    pressure: [wgpu::Texture; 2],
+    smooth_bind_groups: [BindGroup; 2], // 0: [0] -> [1], 1: [1] -> [0]
+    // Everything below is None on the coarsest level
+    residual_bind_group: Option<BindGroup>, // [0] + rhs -> residual
+    restrict_bind_group: Option<BindGroup>, // residual -> next level's rhs
+    prolong_bind_group: Option<BindGroup>,  // [0] + next level's [0] -> [1]
+    mask_restrict_bind_group: Option<BindGroup>, // mask -> next level's mask
+}
+
+// V-cycle multigrid Poisson solver on the GPU.
+// Solves into the sim's pressure_a, using its divergence as the right-hand side.
+// The sim's obstacle mask is restricted onto every coarse level at the start of each solve.
+pub struct MultigridSolver {
+    pipeline: MultigridPipeline,
+    settings: VCycleSettings,
+    levels: Vec<Level>,
+}
+
+impl MultigridSolver {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        device: &Device,
+        width: u32,
+        height: u32,
+        pressure_a: &Texture,
+        pressure_b: &Texture,
+        divergence: &Texture,
+        obstacles: &Texture,
+        obstacle_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let pipeline = MultigridPipeline::new(device, obstacle_layout);
+        let settings = VCycleSettings::default();
+        let level_count = level_count(width as usize, height as usize, &settings);
+
+        // 1. Allocate the coarse grids (level 0 borrows the sim's textures)
+        let mut sizes = vec![(width, height)];
+        for _ in 1..level_count {
+            let (w, h) = sizes[sizes.len() - 1];
+            let (cw, ch) = coarse_size(w as usize, h as usize);
+            sizes.push((cw as u32, ch as u32));
+        }
+
+        let field = |(w, h): (u32, u32), name: &str| {
+            Texture::create_storage_texture(device, w, h, wgpu::TextureFormat::R32Float, Some(name))
+        };
+        let mut coarse_pressure: Vec<[Texture; 2]> = Vec::new();
+        let mut coarse_rhs: Vec<Texture> = Vec::new();
+        let mut coarse_masks: Vec<Texture> = Vec::new();
+        for &size in &sizes[1..] {
+            coarse_pressure.push([
+                field(size, "Multigrid Pressure A"),
+                field(size, "Multigrid Pressure B"),
+            ]);
+            coarse_rhs.push(field(size, "Multigrid RHS"));
+            coarse_masks.push(field(size, "Multigrid Obstacles"));
+        }
+        let residuals: Vec<Texture> = sizes[..level_count - 1]
+            .iter()
+            .map(|&size| field(size, "Multigrid Residual"))
+            .collect();
+
+        let pressure_of = |level: usize| -> [&Texture; 2] {
+            if level == 0 {
+                [pressure_a, pressure_b]
+            } else {
+                let pair = &coarse_pressure[level - 1];
+                [&pair[0], &pair[1]]
+            }
+        };
+        let rhs_of = |level: usize| -> &Texture {
+            if level == 0 {
+                divergence
+            } else {
+                &coarse_rhs[level - 1]
+            }
+        };
+        let mask_of = |level: usize| -> &Texture {
+            if level == 0 {
+                obstacles
+            } else {
+                &coarse_masks[level - 1]
+            }
+        };
+
+        // 2. Bind groups
+        let create_bg = |uniforms: &wgpu::Buffer, a: &Texture, b: &Texture, out: &Texture| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Multigrid BG"),
+                layout: &pipeline.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniforms.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&a.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&b.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&out.view),
+                    },
+                ],
+            })
+        };
+
+        let levels = (0..level_count)
+            .map(|level| {
+                let (w, h) = sizes[level];
+                let uniforms = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Multigrid Uniforms"),
+                    contents: bytemuck::cast_slice(&[MultigridUniforms {
+                        width: w as f32,
+                        height: h as f32,
+                        h2: 4.0_f32.powi(level as i32),
+                        omega: settings.omega,
+                        boundary: [0; 4],
+                    }]),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                });
+
+                let [p0, p1] = pressure_of(level);
+                let rhs = rhs_of(level);
+                let mask = mask_of(level);
+                let has_coarser = level + 1 < level_count;
+
+                Level {
+                    width: w,
+                    height: h,
+                    pressure: [p0.texture.clone(), p1.texture.clone()],
+                    smooth_bind_groups: [
+                        create_bg(&uniforms, p0, rhs, p1),
+                        create_bg(&uniforms, p1, rhs, p0),
+                    ],
+                    residual_bind_group: has_coarser
+                        .then(|| create_bg(&uniforms, p0, rhs, &residuals[level])),
+                    // Field B is unused by restrict; bind the residual again as a placeholder
+                    restrict_bind_group: has_coarser.then(|| {
+                        create_bg(
+                            &uniforms,
+                            &residuals[level],
+                            &residuals[level],
+                            rhs_of(level + 1),
+                        )
+                    }),
+                    prolong_bind_group: has_coarser
+                        .then(|| create_bg(&uniforms, p0, pressure_of(level + 1)[0], p1)),
+                    mask_restrict_bind_group: has_coarser
+                        .then(|| create_bg(&uniforms, mask, mask, mask_of(level + 1))),
+                    mask_bind_group: device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("Multigrid Obstacle BG"),
+                        layout: obstacle_layout,
+                        entries: &[wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&mask.view),
+                        }],
+                    }),
+                    uniforms,
+                }
+            })
+            .collect();
+
+        Self {
+            pipeline,
+            settings,
+            levels,
+        }
+    }
+
+    // Per-edge boundary modes (BoundaryMode as u32), applied on every level
+    pub fn set_boundary(&self, queue: &Queue, boundary: [u32; 4]) {
+        let offset = std::mem::offset_of!(MultigridUniforms, boundary) as wgpu::BufferAddress;
+        for level in &self.levels {
+            queue.write_buffer(&level.uniforms, offset, bytemuck::cast_slice(&boundary));
+        }
+    }
+
+    // Runs 'cycles' V-cycles, warm-started from the current pressure. Result ends in pressure_a.
+    pub fn solve(&self, encoder: &mut CommandEncoder, cycles: u32) {
+        self.restrict_masks(encoder);
+        for _ in 0..cycles {
+            self.v_cycle(encoder, 0);
+        }
+    }
+
+    // Carries the obstacle mask down the hierarchy (each coarse cell = fraction of solid fine cells)
+    fn restrict_masks(&self, encoder: &mut CommandEncoder) {
+        for pair in self.levels.windows(2) {
+            let [current, coarser] = pair else {
+                continue;
+            };
+            if let Some(bind_group) = &current.mask_restrict_bind_group {
+                // Bind the fine mask as group(1): the coarse one is being written
+                self.dispatch(
+                    encoder,
+                    &self.pipeline.restrict_pipeline,
+                    [bind_group, &current.mask_bind_group],
+                    coarser,
+                );
+            }
+        }
+    }
+
+    fn v_cycle(&self, encoder: &mut CommandEncoder, level: usize) {
+        let current = &self.levels[level];
+        let (Some(residual_bg), Some(restrict_bg), Some(prolong_bg)) = (
+            &current.residual_bind_group,
+            &current.restrict_bind_group,
+            &current.prolong_bind_group,
+        ) else {
+            // Coarsest level: just smooth it a lot
+            self.smooth(encoder, level, self.settings.coarse_iterations);
+            return;
+        };
+        let coarser = &self.levels[level + 1];
+
+        // 1. Pre-smooth
+        self.smooth(encoder, level, self.settings.pre_smooth);
+
+        // 2. Residual, then restrict it to the next level's rhs
+        self.dispatch(
+            encoder,
+            &self.pipeline.residual_pipeline,
+            [residual_bg, &current.mask_bind_group],
+            current,
+        );
+        self.dispatch(
+            encoder,
+            &self.pipeline.restrict_pipeline,
+            [restrict_bg, &coarser.mask_bind_group],
+            coarser,
+        );
+
+        // 3. Solve for the correction on the coarser grid, starting from zero
+        encoder.clear_texture(
+            &coarser.pressure[0],
+            &wgpu::ImageSubresourceRange::default(),
+        );
+        self.v_cycle(encoder, level + 1);
+
+        // 4. Add the interpolated correction ([0] -> [1]), then restore the invariant
+        self.dispatch(
+            encoder,
+            &self.pipeline.prolong_pipeline,
+            [prolong_bg, &current.mask_bind_group],
+            current,
+        );
+        Self::copy_back(encoder, current);
+
+        // 5. Post-smooth
+        self.smooth(encoder, level, self.settings.post_smooth);
+    }
+
+    fn smooth(&self, encoder: &mut CommandEncoder, level: usize, iterations: usize) {
+        let current = &self.levels[level];
+        for i in 0..iterations {
+            let bind_group = &current.smooth_bind_groups[i % 2];
+            self.dispatch(
+                encoder,
+                &self.pipeline.smooth_pipeline,
+                [bind_group, &current.mask_bind_group],
+                current,
+            );
+        }
+        // Odd count: the result is in [1]
+        if iterations % 2 == 1 {
+            Self::copy_back(encoder, current);
+        }
+    }
+
+    fn copy_back(encoder: &mut CommandEncoder, level: &Level) {
+        encoder.copy_texture_to_texture(
+            level.pressure[1].as_image_copy(),
+            level.pressure[0].as_image_copy(),
+            level.pressure[0].size(),
+        );
+    }
+
+    // 'target' is the level whose grid is written (sets the dispatch size).
+    // 'bind_groups' are group(0) and the obstacle mask for group(1).
+    fn dispatch(
+        &self,
+        encoder: &mut CommandEncoder,
+        pipeline: &ComputePipeline,
+        bind_groups: [&BindGroup; 2],
+        target: &Level,
+    ) {
+        let x_groups = (target.width as f32 / 16.0).ceil() as u32;
+        let y_groups = (target.height as f32 / 16.0).ceil() as u32;
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Multigrid Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, bind_groups[0], &[]);
+        pass.set_bind_group(1, bind_groups[1], &[]);
+        pass.dispatch_workgroups(x_groups, y_groups, 1);
+    }
+}
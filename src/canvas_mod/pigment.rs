@@ -0,0 +1,170 @@
+// Kubelka–Munk pigment model, CPU side: the reference for km_reflectance and palette_reflectance in shader.wgsl.
+//
+// In pigment mode a density cell holds, per RGB channel, the K/S ratio (absorption over
+// scattering) of the paint in it, already weighted by concentration, and the total pigment
+// concentration in A. K/S mixes linearly with concentration, so advection, diffusion and the
+// brush blend paints just by interpolating the stored values, and blue plus yellow makes green.
+// Clean paper is all zeros in both modes.
+
+// Darkest reflectance a pigment can have. Pure black would need an infinite K/S.
+const MIN_REFLECTANCE: f32 = 0.01;
+
+// Reflectance of an infinitely thick layer -> its K/S ratio
+pub fn reflectance_to_ks(reflectance: f32) -> f32 {
+    let r = reflectance.clamp(MIN_REFLECTANCE, 1.0);
+    (1.0 - r) * (1.0 - r) / (2.0 * r)
+}
+
+// K/S ratio -> reflectance (the inverse of reflectance_to_ks)
+pub fn ks_to_reflectance(ks: f32) -> f32 {
+    let ks = ks.max(0.0);
+    1.0 + ks - (ks * ks + 2.0 * ks).sqrt()
+}
+
+// Brush colour (RGB + load in A) -> what the brush deposits: the pigment at full concentration.
+// The load stays the blend factor, exactly as in RGB mode.
+pub fn brush_pigment(color: [f32; 4]) -> [f32; 4] {
+    [
+        reflectance_to_ks(color[0]),
+        reflectance_to_ks(color[1]),
+        reflectance_to_ks(color[2]),
+        color[3],
+    ]
+}
+
+// RGB cell (ink colour + amount over white paper) -> pigment cell that looks the same
+pub fn rgb_cell_to_pigment(cell: [f32; 4]) -> [f32; 4] {
+    let amount = cell[3].clamp(0.0, 1.0);
+    let ks = |c: f32| reflectance_to_ks(1.0 + (c - 1.0) * amount);
+    [ks(cell[0]), ks(cell[1]), ks(cell[2]), amount]
+}
+
+// Pigment cell -> RGB cell that looks the same (as far as the RGB range allows)
+pub fn pigment_cell_to_rgb(cell: [f32; 4]) -> [f32; 4] {
+    let amount = cell[3].clamp(0.0, 1.0);
+    if amount <= 0.0 {
+        return [0.0; 4];
+    }
+    // Solve mix(paper, rgb, amount) = reflectance for rgb
+    let rgb = |ks: f32| ((ks_to_reflectance(ks) - (1.0 - amount)) / amount).clamp(0.0, 1.0);
+    [rgb(cell[0]), rgb(cell[1]), rgb(cell[2]), amount]
+}
+
+// --- PALETTE MODE ---
+// Density holds the concentrations of pigments 0-3 and the second density texture those of
+// 4-7. Each pigment has its own absorption K and scattering S per channel, so unlike the K/S
+// mode above, white (low K, high S) really lightens a mix. The render shader composites them
+// with the same formula as palette_reflectance below.
+
+pub const PALETTE_SIZE: usize = 8;
+
+pub struct Pigment {
+    pub name: &'static str,
+    pub masstone: [f32; 3], // Reflectance of the pure paint (linear RGB)
+    pub scattering: f32,    // Hiding power; K follows from the masstone
+}
+
+pub const PALETTE: [Pigment; PALETTE_SIZE] = [
+    Pigment {
+        name: "Titanium White",
+        masstone: [0.95, 0.95, 0.94],
+        scattering: 3.0,
+    },
+    Pigment {
+        name: "Cadmium Yellow",
+        masstone: [0.92, 0.75, 0.04],
+        scattering: 1.2,
+    },
+    Pigment {
+        name: "Cadmium Red",
+        masstone: [0.75, 0.07, 0.04],
+        scattering: 1.2,
+    },
+    Pigment {
+        name: "Quinacridone Magenta",
+        masstone: [0.55, 0.04, 0.25],
+        scattering: 0.5,
+    },
+    Pigment {
+        name: "Ultramarine Blue",
+        masstone: [0.08, 0.10, 0.60],
+        scattering: 0.6,
+    },
+    Pigment {
+        name: "Phthalo Blue",
+        masstone: [0.02, 0.12, 0.35],
+        scattering: 0.3,
+    },
+    Pigment {
+        name: "Phthalo Green",
+        masstone: [0.02, 0.30, 0.18],
+        scattering: 0.3,
+    },
+    Pigment {
+        name: "Ivory Black",
+        masstone: [0.03, 0.03, 0.03],
+        scattering: 1.0,
+    },
+];
+
+// Scattering of the bare paper/medium where the concentrations add up to less than 1
+const PAPER_SCATTERING: f32 = 1.0;
+
+// Absorption and scattering per channel
+pub fn pigment_optics(pigment: &Pigment) -> ([f32; 3], [f32; 3]) {
+    let s = pigment.scattering;
+    let k = pigment.masstone.map(|r| reflectance_to_ks(r) * s);
+    (k, [s; 3])
+}
+
+// Reflectance of a cell holding the given pigment concentrations.
+pub fn palette_reflectance(concentrations: [f32; PALETTE_SIZE]) -> [f32; 3] {
+    let mut k = [0.0; 3];
+    let mut s = [0.0; 3];
+    let mut total = 0.0;
+    for (pigment, &c) in PALETTE.iter().zip(&concentrations) {
+        let c = c.max(0.0);
+        let (pk, ps) = pigment_optics(pigment);
+        for channel in 0..3 {
+            k[channel] += c * pk[channel];
+            s[channel] += c * ps[channel];
+        }
+        total += c;
+    }
+    let paper = (1.0 - total).max(0.0) * PAPER_SCATTERING;
+    [0, 1, 2].map(|channel| ks_to_reflectance(k[channel] / (s[channel] + paper)))
+}
+
+// Palette cell (both density textures) -> RGB cell that looks the same
+pub fn palette_cell_to_rgb(low: [f32; 4], high: [f32; 4]) -> [f32; 4] {
+    let concentrations = [
+        low[0], low[1], low[2], low[3], high[0], high[1], high[2], high[3],
+    ];
+    let amount = concentrations.iter().sum::<f32>().clamp(0.0, 1.0);
+    if amount <= 0.0 {
+        return [0.0; 4];
+    }
+    let reflectance = palette_reflectance(concentrations);
+    let rgb = |r: f32| ((r - (1.0 - amount)) / amount).clamp(0.0, 1.0);
+    [
+        rgb(reflectance[0]),
+        rgb(reflectance[1]),
+        rgb(reflectance[2]),
+        amount,
+    ]
+}
+
+// Palette cell -> K/S cell with the same reflectance, on any paper colour
+pub fn palette_cell_to_pigment(low: [f32; 4], high: [f32; 4]) -> [f32; 4] {
+    let concentrations = [
+        low[0], low[1], low[2], low[3], high[0], high[1], high[2], high[3],
+    ];
+    let amount = concentrations.iter().sum::<f32>().clamp(0.0, 1.0);
+    let [r, g, b] = palette_reflectance(concentrations);
+    [
+        reflectance_to_ks(r),
+        reflectance_to_ks(g),
+        reflectance_to_ks(b),
+        amount,
+    ]
+}
@@ -8,106 +8,167 @@ pub struct AdvectionUniforms {
     pub height: f32,
     pub velocity_decay: f32, // Friction (e.g. 0.99)
     pub ink_decay: f32,      // Evaporation (e.g. 1.0)
-    pub _padding: [f32; 3],  // Optional: Pad to 32 bytes to be safe/clean
+    pub grain_friction: f32, // Drag on the paper grain's ridges, per second
+    pub _padding: [f32; 2],  // Optional: Pad to 32 bytes to be safe/clean
+    pub boundary: [u32; 4],  // BoundaryMode per edge: left, right, top, bottom
 }
 
 pub struct AdvectionPipeline {
     pub pipeline: wgpu::ComputePipeline,
     pub bind_group_layout: wgpu::BindGroupLayout,
+
+    // MacCormack: predict_main uses the layout above (A -> B, no dissipation),
+    // maccormack_main adds the forward result as bindings 6/7 and writes to the temp textures
+    pub predict_pipeline: wgpu::ComputePipeline,
+    pub maccormack_pipeline: wgpu::ComputePipeline,
+    pub maccormack_layout: wgpu::BindGroupLayout,
+
+    // Same three passes with WRITE_VELOCITY off, for the second pigment field (palette mode)
+    pub ink_pipeline: wgpu::ComputePipeline,
+    pub ink_predict_pipeline: wgpu::ComputePipeline,
+    pub ink_maccormack_pipeline: wgpu::ComputePipeline,
+
     pub uniform_buffer: wgpu::Buffer,
 }
 
 impl AdvectionPipeline {
-    pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        obstacle_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Advection Shader"),
             // Make sure this path matches your file structure!
-            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/advect.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shaders/boundary.wgsl"),
+                    include_str!("../shaders/obstacles.wgsl"),
+                    include_str!("../shaders/advect.wgsl")
+                )
+                .into(),
+            ),
         });
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Advection Layout"),
-            entries: &[
-                // 0: Uniforms
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                // 1: Velocity IN
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                // 2: Density IN
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                // 3: Velocity OUT
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::StorageTexture {
-                        access: wgpu::StorageTextureAccess::WriteOnly,
-                        format: wgpu::TextureFormat::Rg32Float,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
+        let filterable_texture = |binding: u32| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let entries = [
+            // 0: Uniforms
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
                 },
-                // 4: Density OUT
-                wgpu::BindGroupLayoutEntry {
-                    binding: 4,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::StorageTexture {
-                        access: wgpu::StorageTextureAccess::WriteOnly,
-                        format: wgpu::TextureFormat::Rgba32Float,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
+                count: None,
+            },
+            // 1: Velocity IN
+            filterable_texture(1),
+            // 2: Density IN
+            filterable_texture(2),
+            // 3: Velocity OUT
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: wgpu::TextureFormat::Rg32Float,
+                    view_dimension: wgpu::TextureViewDimension::D2,
                 },
-                // 5: Sampler
-                wgpu::BindGroupLayoutEntry {
-                    binding: 5,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
+                count: None,
+            },
+            // 4: Density OUT
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: wgpu::TextureFormat::Rgba32Float,
+                    view_dimension: wgpu::TextureViewDimension::D2,
                 },
-            ],
-        });
+                count: None,
+            },
+            // 8: Paper Grain
+            filterable_texture(8),
+        ];
 
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Advection Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Advection Layout"),
+            entries: &entries,
         });
 
-        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Advection Pipeline"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("main"),
-            compilation_options: Default::default(),
-            cache: None,
+        let maccormack_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("MacCormack Layout"),
+            entries: &[
+                entries.as_slice(),
+                // 6: Velocity HAT, 7: Density HAT (the forward result)
+                &[filterable_texture(6), filterable_texture(7)],
+            ]
+            .concat(),
         });
 
+        // group(1): the obstacle mask
+        let create_pipeline =
+            |label: &str, layout: &wgpu::BindGroupLayout, entry: &str, write_velocity: bool| {
+                let pipeline_layout =
+                    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                        label: Some(label),
+                        bind_group_layouts: &[layout, obstacle_layout],
+                        push_constant_ranges: &[],
+                    });
+                device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(&pipeline_layout),
+                    module: &shader,
+                    entry_point: Some(entry),
+                    compilation_options: wgpu::PipelineCompilationOptions {
+                        constants: &[("WRITE_VELOCITY", f64::from(write_velocity))],
+                        ..Default::default()
+                    },
+                    cache: None,
+                })
+            };
+
+        let pipeline = create_pipeline("Advection Pipeline", &bind_group_layout, "main", true);
+        let predict_pipeline = create_pipeline(
+            "MacCormack Predict Pipeline",
+            &bind_group_layout,
+            "predict_main",
+            true,
+        );
+        let maccormack_pipeline = create_pipeline(
+            "MacCormack Correct Pipeline",
+            &maccormack_layout,
+            "maccormack_main",
+            true,
+        );
+        let ink_pipeline =
+            create_pipeline("Ink Advection Pipeline", &bind_group_layout, "main", false);
+        let ink_predict_pipeline = create_pipeline(
+            "Ink MacCormack Predict Pipeline",
+            &bind_group_layout,
+            "predict_main",
+            false,
+        );
+        let ink_maccormack_pipeline = create_pipeline(
+            "Ink MacCormack Correct Pipeline",
+            &maccormack_layout,
+            "maccormack_main",
+            false,
+        );
+
         let initial_data = AdvectionUniforms {
             dt: 0.016,
             width: width as f32,
@@ -117,7 +178,9 @@ impl AdvectionPipeline {
             velocity_decay: 0.7, // Slows down and stops after ~2-3 seconds
             ink_decay: 1.0,      // Paint stays forever
 
-            _padding: [0.0; 3],
+            grain_friction: 0.0,
+            _padding: [0.0; 2],
+            boundary: [0; 4],
         };
 
         let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -129,6 +192,12 @@ impl AdvectionPipeline {
         Self {
             pipeline,
             bind_group_layout,
+            predict_pipeline,
+            maccormack_pipeline,
+            maccormack_layout,
+            ink_pipeline,
+            ink_predict_pipeline,
+            ink_maccormack_pipeline,
             uniform_buffer,
         }
     }
@@ -1,22 +1,31 @@
+use crate::canvas_mod::stroke::MAX_STROKE_POINTS;
 use wgpu::util::DeviceExt;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct BrushUniforms {
-    pub mouse_pos: [f32; 2],      // 8 bytes
-    pub last_mouse_pos: [f32; 2], // 8 bytes
-    pub velocity_factor: f32,     // 4 bytes
-    pub radius: f32,              // 4 bytes
-    pub smudge: f32,
-    // MANUAL PADDING to align the next vec4 to 16-byte boundary
-    pub _padding: [f32; 1],    // 8 bytes
+    pub point_count: u32,      // Points of the stroke buffer in use
+    pub path_length: f32,      // Length of the stroke in cells
+    pub velocity_factor: f32,  // 4 bytes
+    pub radius: f32,           // 4 bytes
+    pub tool: u32,             // BrushTool as u32
+    pub palette: u32,          // bool (0/1): paint pigment concentrations
+    pub amount: f32,           // Palette mode: the blend amount
+    pub _padding: f32,         // brush_color starts at 32 bytes
     pub brush_color: [f32; 4], // 16 bytes
+    // Palette mode: target concentrations of pigments 4-7
+    pub brush_color2: [f32; 4],
+    pub bounds: [f32; 4], // Stroke bounding box grown by the radius: min xy, max xy
 }
 
 pub struct BrushPipeline {
     pub pipeline: wgpu::ComputePipeline,
     pub bind_group_layout: wgpu::BindGroupLayout,
+    // Wall tools: paint into (or erase from) the obstacle mask, same uniforms
+    pub obstacle_pipeline: wgpu::ComputePipeline,
+    pub obstacle_layout: wgpu::BindGroupLayout,
     pub brush_buffer: wgpu::Buffer,
+    pub stroke_buffer: wgpu::Buffer, // MAX_STROKE_POINTS x [f32; 2]
 }
 
 impl BrushPipeline {
@@ -26,6 +35,17 @@ impl BrushPipeline {
             source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/brush.wgsl").into()),
         });
 
+        let stroke_entry = wgpu::BindGroupLayoutEntry {
+            binding: 8,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Brush Bind Group Layout"),
             entries: &[
@@ -85,6 +105,41 @@ impl BrushPipeline {
                     },
                     count: None,
                 },
+                // Binding 5: Second Density IN (pigments 4-7)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Binding 6: Second Density OUT
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba32Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // Binding 7: Wetness OUT (under the stroke only)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::R32Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // Binding 8: Stroke points
+                stroke_entry,
             ],
         });
 
@@ -103,14 +158,64 @@ impl BrushPipeline {
             cache: None,
         });
 
+        let obstacle_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Obstacle Brush Layout"),
+            entries: &[
+                // Binding 0: Uniforms
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Binding 1: Obstacles OUT
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::R32Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // Binding 8: Stroke points
+                stroke_entry,
+            ],
+        });
+
+        let obstacle_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Obstacle Brush Pipeline Layout"),
+                bind_group_layouts: &[&obstacle_layout],
+                push_constant_ranges: &[],
+            });
+
+        let obstacle_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Obstacle Brush Pipeline"),
+            layout: Some(&obstacle_pipeline_layout),
+            module: &shader,
+            entry_point: Some("obstacle_main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
         let initial_data = BrushUniforms {
-            mouse_pos: [0.0, 0.0],
-            last_mouse_pos: [0.0, 0.0],
+            point_count: 1,
+            path_length: 0.0,
             velocity_factor: 1.0,
             radius: 20.0,
-            smudge: 0.0,
-            _padding: [0.0; 1], // Zero out padding
+            tool: 0,
+            palette: 0,
+            amount: 1.0,
+            _padding: 0.0, // Zero out padding
             brush_color: [0.0, 0.0, 0.0, 1.0],
+            brush_color2: [0.0; 4],
+            bounds: [0.0; 4],
         };
 
         let brush_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -119,10 +224,20 @@ impl BrushPipeline {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
+        let stroke_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Brush Stroke Buffer"),
+            size: (MAX_STROKE_POINTS * std::mem::size_of::<[f32; 2]>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         Self {
             pipeline,
             bind_group_layout,
+            obstacle_pipeline,
+            obstacle_layout,
             brush_buffer,
+            stroke_buffer,
         }
     }
 }
@@ -7,19 +7,53 @@ pub struct DiffuseUniforms {
     pub height: f32,
     pub alpha: f32, // <--- The tuning values
     pub one_over_beta: f32,
+    pub omega: f32,
+    pub _padding: [f32; 3],
+    pub boundary: [u32; 4], // BoundaryMode per edge: left, right, top, bottom
+}
+
+// Storage format of the field being diffused. Picks the shader prelude declaring the output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiffuseFormat {
+    Rgba32Float, // Ink (bleed)
+    Rg32Float,   // Velocity (viscosity)
+}
+
+impl DiffuseFormat {
+    fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            DiffuseFormat::Rgba32Float => wgpu::TextureFormat::Rgba32Float,
+            DiffuseFormat::Rg32Float => wgpu::TextureFormat::Rg32Float,
+        }
+    }
+
+    fn prelude(self) -> &'static str {
+        match self {
+            DiffuseFormat::Rgba32Float => include_str!("../shaders/diffuse_rgba.wgsl"),
+            DiffuseFormat::Rg32Float => include_str!("../shaders/diffuse_rg.wgsl"),
+        }
+    }
 }
 
 pub struct DiffusePipeline {
     pub uniform_buffer: wgpu::Buffer,
     pub pipeline: wgpu::ComputePipeline,
+    pub rb_red_pipeline: wgpu::ComputePipeline,
+    pub rb_black_pipeline: wgpu::ComputePipeline,
     pub bind_group_layout: wgpu::BindGroupLayout,
 }
 
 impl DiffusePipeline {
-    pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, format: DiffuseFormat) -> Self {
+        let source = [
+            include_str!("../shaders/boundary.wgsl"),
+            format.prelude(),
+            include_str!("../shaders/diffuse.wgsl"),
+        ]
+        .concat();
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Diffuse Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/diffuse.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
         });
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -64,7 +98,7 @@ impl DiffusePipeline {
                     visibility: wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::StorageTexture {
                         access: wgpu::StorageTextureAccess::WriteOnly,
-                        format: wgpu::TextureFormat::Rgba32Float, // Matches Density
+                        format: format.texture_format(), // Matches the diffused field
                         view_dimension: wgpu::TextureViewDimension::D2,
                     },
                     count: None,
@@ -78,14 +112,21 @@ impl DiffusePipeline {
             push_constant_ranges: &[],
         });
 
-        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Diffuse Pipeline"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("main"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
+        let create_pipeline = |label: &str, entry: &str| -> wgpu::ComputePipeline {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let pipeline = create_pipeline("Diffuse Pipeline", "main");
+        let rb_red_pipeline = create_pipeline("Diffuse Red-Black Red Pipeline", "rb_red_main");
+        let rb_black_pipeline =
+            create_pipeline("Diffuse Red-Black Black Pipeline", "rb_black_main");
 
         // Initial Data (Alpha/Beta will be overwritten every frame)
         let initial_data = DiffuseUniforms {
@@ -93,6 +134,9 @@ impl DiffusePipeline {
             height: height as f32,
             alpha: 0.0,
             one_over_beta: 0.0,
+            omega: 1.0,
+            _padding: [0.0; 3],
+            boundary: [0; 4],
         };
 
         let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -105,6 +149,8 @@ impl DiffusePipeline {
             uniform_buffer,
             bind_group_layout,
             pipeline,
+            rb_red_pipeline,
+            rb_black_pipeline,
         }
     }
 }
@@ -2,5 +2,11 @@ pub mod advect_pipeline;
 pub mod brush_pipeline;
 pub mod diffuse_pipeline;
 pub mod draw;
+pub mod multigrid_pipeline;
+pub mod obstacle_layout;
+pub mod paper_pipeline;
 pub mod pressure_pipeline;
+pub mod reduce_pipeline;
 pub mod render_pipeline;
+pub mod resample_pipeline;
+pub mod vorticity_pipeline;
@@ -0,0 +1,103 @@
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MultigridUniforms {
+    pub width: f32,
+    pub height: f32,
+    pub h2: f32,
+    pub omega: f32,
+    pub boundary: [u32; 4], // BoundaryMode per edge: left, right, top, bottom
+}
+
+// All four V-cycle passes share one layout: Uniforms, Field A, Field B, Field OUT.
+// group(1) is the obstacle mask of the level being worked on.
+pub struct MultigridPipeline {
+    pub layout: wgpu::BindGroupLayout,
+    pub smooth_pipeline: wgpu::ComputePipeline,
+    pub residual_pipeline: wgpu::ComputePipeline,
+    pub restrict_pipeline: wgpu::ComputePipeline,
+    pub prolong_pipeline: wgpu::ComputePipeline,
+}
+
+impl MultigridPipeline {
+    pub fn new(device: &wgpu::Device, obstacle_layout: &wgpu::BindGroupLayout) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Multigrid Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shaders/boundary.wgsl"),
+                    include_str!("../shaders/obstacles.wgsl"),
+                    include_str!("../shaders/multigrid.wgsl")
+                )
+                .into(),
+            ),
+        });
+
+        let read_only_field = |binding: u32| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Multigrid Layout"),
+            entries: &[
+                // 0: Uniforms (per level)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // 1: Field A (pressure / fine residual)
+                read_only_field(1),
+                // 2: Field B (rhs / coarse correction)
+                read_only_field(2),
+                // 3: Field OUT
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::R32Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Multigrid Pipeline Layout"),
+            bind_group_layouts: &[&layout, obstacle_layout],
+            push_constant_ranges: &[],
+        });
+
+        let create_pipeline = |label: &str, entry: &str| -> wgpu::ComputePipeline {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        Self {
+            smooth_pipeline: create_pipeline("Multigrid Smooth Pipeline", "smooth_main"),
+            residual_pipeline: create_pipeline("Multigrid Residual Pipeline", "residual_main"),
+            restrict_pipeline: create_pipeline("Multigrid Restrict Pipeline", "restrict_main"),
+            prolong_pipeline: create_pipeline("Multigrid Prolong Pipeline", "prolong_main"),
+            layout,
+        }
+    }
+}
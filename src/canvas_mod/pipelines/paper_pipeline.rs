@@ -0,0 +1,111 @@
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PaperUniforms {
+    pub dt: f32,
+    pub deposit_rate: f32,   // Per second, on dry paper
+    pub drying_time: f32,    // Seconds
+    pub color_model: u32,    // ColorModel as u32
+    pub granulation: f32,    // Settling: valleys collect pigment, ridges shed it
+    pub absorption: f32,     // Drying: ridges dry first
+    pub edge_darkening: f32, // Extra settling at the rim of a wet area
+    pub _padding: f32,
+}
+
+// Settling (Liquid ink + Paper + Wetness + Grain -> Liquid ink + Paper + Wetness)
+pub struct PaperPipeline {
+    pub pipeline: wgpu::ComputePipeline,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub uniform_buffer: wgpu::Buffer,
+}
+
+impl PaperPipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Paper Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/paper.wgsl").into()),
+        });
+
+        let read_only_field = |binding: u32| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let write_only_field =
+            |binding: u32, format: wgpu::TextureFormat| wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            };
+
+        // Uniforms, Density / Paper / Wetness IN, Density / Paper / Wetness OUT, Grain IN
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Paper Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                read_only_field(1),
+                read_only_field(2),
+                read_only_field(3),
+                write_only_field(4, wgpu::TextureFormat::Rgba32Float),
+                write_only_field(5, wgpu::TextureFormat::Rgba32Float),
+                write_only_field(6, wgpu::TextureFormat::R32Float),
+                read_only_field(7),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Paper Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Paper Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Paper Uniforms"),
+            contents: bytemuck::cast_slice(&[PaperUniforms {
+                dt: 0.016,
+                deposit_rate: 0.0,
+                drying_time: 1.0,
+                color_model: 0,
+                granulation: 0.0,
+                absorption: 0.0,
+                edge_darkening: 0.0,
+                _padding: 0.0,
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniform_buffer,
+        }
+    }
+}
@@ -6,6 +6,8 @@ pub struct PressureUniforms {
     pub width: f32,
     pub height: f32,
     pub dt: f32,
+    pub omega: f32,
+    pub boundary: [u32; 4], // BoundaryMode per edge: left, right, top, bottom
 }
 
 pub struct PressurePipeline {
@@ -19,16 +21,32 @@ pub struct PressurePipeline {
     pub jacobi_pipeline: wgpu::ComputePipeline,
     pub jacobi_layout: wgpu::BindGroupLayout,
 
+    // 2b. Red-Black Gauss-Seidel / SOR (shares the Jacobi layout)
+    pub rb_red_pipeline: wgpu::ComputePipeline,
+    pub rb_black_pipeline: wgpu::ComputePipeline,
+
     // 3. Subtract Gradient
     pub sub_pipeline: wgpu::ComputePipeline,
     pub sub_layout: wgpu::BindGroupLayout,
 }
 
 impl PressurePipeline {
-    pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        obstacle_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Pressure Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/pressure.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shaders/boundary.wgsl"),
+                    include_str!("../shaders/obstacles.wgsl"),
+                    include_str!("../shaders/pressure.wgsl")
+                )
+                .into(),
+            ),
         });
 
         // --- 1. Divergence Layout ---
@@ -174,11 +192,12 @@ impl PressurePipeline {
         });
 
         // --- Helper to Create Pipelines ---
+        // group(1) is the obstacle mask for every pass
         let create_pipeline =
             |label: &str, layout: &wgpu::BindGroupLayout, entry: &str| -> wgpu::ComputePipeline {
                 let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                     label: Some(label),
-                    bind_group_layouts: &[layout],
+                    bind_group_layouts: &[layout, obstacle_layout],
                     push_constant_ranges: &[],
                 });
                 device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
@@ -194,11 +213,17 @@ impl PressurePipeline {
         let div_pipeline = create_pipeline("Divergence Pipeline", &div_layout, "divergence_main");
         let jacobi_pipeline = create_pipeline("Jacobi Pipeline", &jacobi_layout, "jacobi_main");
         let sub_pipeline = create_pipeline("Subtract Pipeline", &sub_layout, "subtract_main");
+        let rb_red_pipeline =
+            create_pipeline("Red-Black Red Pipeline", &jacobi_layout, "rb_red_main");
+        let rb_black_pipeline =
+            create_pipeline("Red-Black Black Pipeline", &jacobi_layout, "rb_black_main");
 
         let initial_data = PressureUniforms {
             width: width as f32,
             height: height as f32,
             dt: 0.016,
+            omega: 1.0,
+            boundary: [0; 4],
         };
 
         let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -213,6 +238,8 @@ impl PressurePipeline {
             div_layout,
             jacobi_pipeline,
             jacobi_layout,
+            rb_red_pipeline,
+            rb_black_pipeline,
             sub_pipeline,
             sub_layout,
         }
@@ -0,0 +1,84 @@
+// Two-pass reduction of an R32Float field into (sum of squares, max |value|).
+// Pass 1 (partial): Field IN -> one vec2 per 16x16 tile. Pass 2 (final): tiles -> one vec4.
+pub struct ReducePipeline {
+    pub partial_layout: wgpu::BindGroupLayout,
+    pub partial_pipeline: wgpu::ComputePipeline,
+    pub final_layout: wgpu::BindGroupLayout,
+    pub final_pipeline: wgpu::ComputePipeline,
+}
+
+impl ReducePipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Reduce Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/reduce.wgsl").into()),
+        });
+
+        let storage_buffer = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let partial_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Reduce Partial Layout"),
+            entries: &[
+                // 0: Field IN
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // 1: Partials OUT
+                storage_buffer(1, false),
+            ],
+        });
+
+        let final_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Reduce Final Layout"),
+            entries: &[
+                // 0: Partials IN
+                storage_buffer(0, true),
+                // 1: Result OUT
+                storage_buffer(1, false),
+            ],
+        });
+
+        let create_pipeline = |label: &str, layout: &wgpu::BindGroupLayout, entry: &str| {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        Self {
+            partial_pipeline: create_pipeline(
+                "Reduce Partial Pipeline",
+                &partial_layout,
+                "partial_main",
+            ),
+            final_pipeline: create_pipeline("Reduce Final Pipeline", &final_layout, "final_main"),
+            partial_layout,
+            final_layout,
+        }
+    }
+}
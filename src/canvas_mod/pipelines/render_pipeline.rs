@@ -1,3 +1,4 @@
+use crate::canvas_mod::pigment::PALETTE_SIZE;
 use crate::canvas_mod::resources::quad::Vertex;
 
 #[repr(C)]
@@ -5,9 +6,23 @@ use crate::canvas_mod::resources::quad::Vertex;
 pub struct ViewUniforms {
     pub screen_size: [f32; 2],
     pub canvas_size: [f32; 2],
-    pub pan: [f32; 2],
+    pub pan: [f32; 2], // ViewTransform::pan, screen pixels
     pub zoom: f32,
-    pub _padding: u32,
+    pub show_obstacles: u32,    // bool (0/1)
+    pub show_mask_preview: u32, // bool (0/1)
+    pub color_model: u32,       // ColorModel as u32: what the density fields hold
+    pub grain_shading: f32,     // Darkening of the paper grain's valleys
+    pub rotation: f32,          // ViewTransform::rotation, radians
+    pub paper_color: [f32; 4],  // Linear RGB, A unused
+}
+
+// Optical constants of the palette pigments (canvas_mod/pigment.rs), one vec4 per pigment
+// (RGB used). Fixed, so written once when the canvas is created.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PaletteUniforms {
+    pub absorption: [[f32; 4]; PALETTE_SIZE],
+    pub scattering: [[f32; 4]; PALETTE_SIZE],
 }
 
 pub fn create_render_setup(
@@ -20,6 +35,17 @@ pub fn create_render_setup(
         source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/shader.wgsl").into()),
     });
 
+    let paper_entry = |binding: u32| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    };
+
     let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("Render Bind Group Layout"),
         entries: &[
@@ -44,7 +70,51 @@ pub fn create_render_setup(
             // 2: View Uniforms
             wgpu::BindGroupLayoutEntry {
                 binding: 2,
-                visibility: wgpu::ShaderStages::VERTEX,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            // 3: Obstacle Mask
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            // 4: Imported Mask Preview (not yet applied)
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            // 5: Second Density Texture (palette pigments 4-7)
+            wgpu::BindGroupLayoutEntry {
+                binding: 5,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            // 6: Palette Uniforms
+            wgpu::BindGroupLayoutEntry {
+                binding: 6,
+                visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Buffer {
                     ty: wgpu::BufferBindingType::Uniform,
                     has_dynamic_offset: false,
@@ -52,6 +122,11 @@ pub fn create_render_setup(
                 },
                 count: None,
             },
+            // 7: Paper (settled ink), 8: Paper 2 (settled pigments 4-7)
+            paper_entry(7),
+            paper_entry(8),
+            // 9: Paper Grain
+            paper_entry(9),
         ],
     });
 
@@ -0,0 +1,144 @@
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ResampleUniforms {
+    pub velocity_scale: [f32; 2],
+    pub _padding: [f32; 2],
+}
+
+// Bilinear copy of a field onto a grid of a different size.
+// Only used when the canvas dimensions change, so it is built on demand and thrown away.
+pub struct ResamplePipeline {
+    pub uniform_buffer: wgpu::Buffer,
+
+    pub density_pipeline: wgpu::ComputePipeline,
+    pub density_layout: wgpu::BindGroupLayout,
+
+    pub velocity_pipeline: wgpu::ComputePipeline,
+    pub velocity_layout: wgpu::BindGroupLayout,
+
+    pub mask_pipeline: wgpu::ComputePipeline,
+    pub mask_layout: wgpu::BindGroupLayout,
+    // Single channel without the threshold; uses mask_layout
+    pub scalar_pipeline: wgpu::ComputePipeline,
+}
+
+impl ResamplePipeline {
+    pub fn new(device: &wgpu::Device, velocity_scale: [f32; 2]) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Resample Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/resample.wgsl").into()),
+        });
+
+        // Both layouts are identical except for the storage format of the output
+        let create_layout = |label: &str, format: wgpu::TextureFormat| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries: &[
+                    // 0: Uniforms
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // 1: Source (Old Grid)
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // 2: Sampler
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    // 3: Destination (New Grid)
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::StorageTexture {
+                            access: wgpu::StorageTextureAccess::WriteOnly,
+                            format,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                        },
+                        count: None,
+                    },
+                ],
+            })
+        };
+
+        let density_layout =
+            create_layout("Resample Density Layout", wgpu::TextureFormat::Rgba32Float);
+        let velocity_layout =
+            create_layout("Resample Velocity Layout", wgpu::TextureFormat::Rg32Float);
+        let mask_layout = create_layout("Resample Mask Layout", wgpu::TextureFormat::R32Float);
+
+        let create_pipeline =
+            |label: &str, layout: &wgpu::BindGroupLayout, entry: &str| -> wgpu::ComputePipeline {
+                let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some(label),
+                    bind_group_layouts: &[layout],
+                    push_constant_ranges: &[],
+                });
+                device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(&layout),
+                    module: &shader,
+                    entry_point: Some(entry),
+                    compilation_options: Default::default(),
+                    cache: None,
+                })
+            };
+
+        let density_pipeline = create_pipeline(
+            "Resample Density Pipeline",
+            &density_layout,
+            "resample_density_main",
+        );
+        let velocity_pipeline = create_pipeline(
+            "Resample Velocity Pipeline",
+            &velocity_layout,
+            "resample_velocity_main",
+        );
+        let mask_pipeline =
+            create_pipeline("Resample Mask Pipeline", &mask_layout, "resample_mask_main");
+        let scalar_pipeline = create_pipeline(
+            "Resample Scalar Pipeline",
+            &mask_layout,
+            "resample_scalar_main",
+        );
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Resample Uniforms"),
+            contents: bytemuck::cast_slice(&[ResampleUniforms {
+                velocity_scale,
+                _padding: [0.0; 2],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        Self {
+            uniform_buffer,
+            density_pipeline,
+            density_layout,
+            velocity_pipeline,
+            velocity_layout,
+            mask_pipeline,
+            mask_layout,
+            scalar_pipeline,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VorticityUniforms {
+    pub strength: f32,
+    pub dt: f32,
+    pub _padding: [f32; 2],
+    pub boundary: [u32; 4], // BoundaryMode per edge: left, right, top, bottom
+}
+
+pub struct VorticityPipeline {
+    pub uniform_buffer: wgpu::Buffer,
+
+    // 1. Curl (Velocity -> Curl)
+    pub curl_pipeline: wgpu::ComputePipeline,
+    pub curl_layout: wgpu::BindGroupLayout,
+
+    // 2. Confinement (Velocity + Curl -> Velocity)
+    pub confine_pipeline: wgpu::ComputePipeline,
+    pub confine_layout: wgpu::BindGroupLayout,
+}
+
+impl VorticityPipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Vorticity Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shaders/boundary.wgsl"),
+                    include_str!("../shaders/vorticity.wgsl")
+                )
+                .into(),
+            ),
+        });
+
+        let uniforms = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let read_only_field = |binding: u32| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let write_only_field =
+            |binding: u32, format: wgpu::TextureFormat| wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            };
+
+        // --- 1. Curl Layout: Uniforms, Velocity IN, Curl OUT ---
+        let curl_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Curl Layout"),
+            entries: &[
+                uniforms,
+                read_only_field(1),
+                write_only_field(2, wgpu::TextureFormat::R32Float),
+            ],
+        });
+
+        // --- 2. Confinement Layout: Uniforms, Velocity IN, Curl IN, Velocity OUT ---
+        let confine_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Confinement Layout"),
+            entries: &[
+                uniforms,
+                read_only_field(1),
+                read_only_field(2),
+                write_only_field(3, wgpu::TextureFormat::Rg32Float),
+            ],
+        });
+
+        let create_pipeline =
+            |label: &str, layout: &wgpu::BindGroupLayout, entry: &str| -> wgpu::ComputePipeline {
+                let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some(label),
+                    bind_group_layouts: &[layout],
+                    push_constant_ranges: &[],
+                });
+                device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(&layout),
+                    module: &shader,
+                    entry_point: Some(entry),
+                    compilation_options: Default::default(),
+                    cache: None,
+                })
+            };
+
+        let curl_pipeline = create_pipeline("Curl Pipeline", &curl_layout, "curl_main");
+        let confine_pipeline =
+            create_pipeline("Confinement Pipeline", &confine_layout, "confine_main");
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vorticity Uniforms"),
+            contents: bytemuck::cast_slice(&[VorticityUniforms {
+                strength: 0.0,
+                dt: 0.016,
+                _padding: [0.0; 2],
+                boundary: [0; 4],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            uniform_buffer,
+            curl_pipeline,
+            curl_layout,
+            confine_pipeline,
+            confine_layout,
+        }
+    }
+}
@@ -0,0 +1,223 @@
+// CPU reference for the pressure Poisson solve.
+// pressure.wgsl / multigrid.wgsl follow it cell for cell; tests/pressure_solver.rs checks the V-cycle.
+//
+// Equation on a level with spacing h:  (pL + pR + pB + pT - 4 pC) / h^2 = rhs
+// Boundary: Pure Neumann. A neighbour outside the grid reads the centre pressure instead.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<f32>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0.0; width * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        self.data[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: f32) {
+        self.data[y * self.width + x] = value;
+    }
+
+    // Sum of the four neighbours, using the centre value for any neighbour outside the grid
+    fn neighbour_sum(&self, x: usize, y: usize) -> f32 {
+        let c = self.get(x, y);
+        let l = if x == 0 { c } else { self.get(x - 1, y) };
+        let r = if x == self.width - 1 {
+            c
+        } else {
+            self.get(x + 1, y)
+        };
+        let b = if y == 0 { c } else { self.get(x, y - 1) };
+        let t = if y == self.height - 1 {
+            c
+        } else {
+            self.get(x, y + 1)
+        };
+        l + r + b + t
+    }
+
+    // Root-mean-square of all cells
+    pub fn rms(&self) -> f32 {
+        let sum_sq: f32 = self.data.iter().map(|v| v * v).sum();
+        (sum_sq / self.data.len() as f32).sqrt()
+    }
+}
+
+// Plain Jacobi, exactly what jacobi_main does (h = 1)
+pub fn jacobi(p: &mut Grid, rhs: &Grid, iterations: usize) {
+    weighted_jacobi(p, rhs, 1.0, 1.0, iterations);
+}
+
+// Red-black Gauss-Seidel with over-relaxation, exactly what rb_red_main + rb_black_main do (h = 1).
+// One iteration updates every red cell ((x + y) even), then every black cell, in place.
+pub fn red_black_sor(p: &mut Grid, rhs: &Grid, omega: f32, iterations: usize) {
+    for _ in 0..iterations {
+        for color in 0..2 {
+            for y in 0..p.height {
+                for x in 0..p.width {
+                    if (x + y) % 2 != color {
+                        continue;
+                    }
+                    let gauss_seidel = (p.neighbour_sum(x, y) - rhs.get(x, y)) * 0.25;
+                    p.set(x, y, p.get(x, y) + omega * (gauss_seidel - p.get(x, y)));
+                }
+            }
+        }
+    }
+}
+
+// Weighted Jacobi smoother: p = (1 - omega) p + omega * (neighbours - h^2 rhs) / 4
+pub fn weighted_jacobi(p: &mut Grid, rhs: &Grid, h2: f32, omega: f32, iterations: usize) {
+    let mut next = p.clone();
+    for _ in 0..iterations {
+        for y in 0..p.height {
+            for x in 0..p.width {
+                let jacobi = (p.neighbour_sum(x, y) - h2 * rhs.get(x, y)) * 0.25;
+                next.set(x, y, p.get(x, y) + omega * (jacobi - p.get(x, y)));
+            }
+        }
+        std::mem::swap(p, &mut next);
+    }
+}
+
+// r = rhs - A p
+pub fn residual(p: &Grid, rhs: &Grid, h2: f32) -> Grid {
+    let mut r = Grid::new(p.width, p.height);
+    for y in 0..p.height {
+        for x in 0..p.width {
+            let laplacian = (p.neighbour_sum(x, y) - 4.0 * p.get(x, y)) / h2;
+            r.set(x, y, rhs.get(x, y) - laplacian);
+        }
+    }
+    r
+}
+
+// Size of the next coarser level
+pub fn coarse_size(width: usize, height: usize) -> (usize, usize) {
+    (width.div_ceil(2), height.div_ceil(2))
+}
+
+// Fine -> Coarse: sum of the 2x2 block / 4.
+// Cells outside the fine grid count as zero (NOT a plain average), which keeps the coarse
+// right-hand side zero-mean on odd sizes. The pure Neumann problem has no solution otherwise.
+pub fn restrict(fine: &Grid) -> Grid {
+    let (cw, ch) = coarse_size(fine.width, fine.height);
+    let mut coarse = Grid::new(cw, ch);
+    for y in 0..ch {
+        for x in 0..cw {
+            let mut sum = 0.0;
+            for (fx, fy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let (sx, sy) = (2 * x + fx, 2 * y + fy);
+                if sx < fine.width && sy < fine.height {
+                    sum += fine.get(sx, sy);
+                }
+            }
+            coarse.set(x, y, sum * 0.25);
+        }
+    }
+    coarse
+}
+
+// Coarse -> Fine: p += bilinear interpolation of the coarse correction (cell centred)
+pub fn prolongate_add(p: &mut Grid, coarse: &Grid) {
+    for y in 0..p.height {
+        for x in 0..p.width {
+            let value = sample_bilinear(
+                coarse,
+                (x as f32 + 0.5) * 0.5 - 0.5,
+                (y as f32 + 0.5) * 0.5 - 0.5,
+            );
+            p.set(x, y, p.get(x, y) + value);
+        }
+    }
+}
+
+fn sample_bilinear(grid: &Grid, x: f32, y: f32) -> f32 {
+    let x = x.clamp(0.0, (grid.width - 1) as f32);
+    let y = y.clamp(0.0, (grid.height - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(grid.width - 1), (y0 + 1).min(grid.height - 1));
+    let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+
+    let bottom = grid.get(x0, y0) * (1.0 - tx) + grid.get(x1, y0) * tx;
+    let top = grid.get(x0, y1) * (1.0 - tx) + grid.get(x1, y1) * tx;
+    bottom * (1.0 - ty) + top * ty
+}
+
+// Settings shared by the CPU reference and the GPU solver
+#[derive(Copy, Clone, Debug)]
+pub struct VCycleSettings {
+    pub pre_smooth: usize,
+    pub post_smooth: usize,
+    pub coarse_iterations: usize,
+    pub omega: f32,
+    // Stop coarsening once either side would drop below this many cells
+    pub min_size: usize,
+}
+
+impl Default for VCycleSettings {
+    fn default() -> Self {
+        Self {
+            pre_smooth: 2,
+            post_smooth: 2,
+            coarse_iterations: 30,
+            omega: 0.8,
+            min_size: 4,
+        }
+    }
+}
+
+// Number of levels (including the finest) for a grid of this size
+pub fn level_count(width: usize, height: usize, settings: &VCycleSettings) -> usize {
+    let (mut w, mut h) = (width, height);
+    let mut levels = 1;
+    while w.min(h) / 2 >= settings.min_size {
+        (w, h) = coarse_size(w, h);
+        levels += 1;
+    }
+    levels
+}
+
+// One V-cycle on the finest level (h = 1)
+pub fn v_cycle(p: &mut Grid, rhs: &Grid, settings: &VCycleSettings) {
+    let levels = level_count(p.width, p.height, settings);
+    v_cycle_level(p, rhs, 1.0, levels - 1, settings);
+}
+
+fn v_cycle_level(
+    p: &mut Grid,
+    rhs: &Grid,
+    h2: f32,
+    levels_below: usize,
+    settings: &VCycleSettings,
+) {
+    if levels_below == 0 {
+        weighted_jacobi(p, rhs, h2, settings.omega, settings.coarse_iterations);
+        return;
+    }
+
+    weighted_jacobi(p, rhs, h2, settings.omega, settings.pre_smooth);
+
+    let coarse_rhs = restrict(&residual(p, rhs, h2));
+    let mut correction = Grid::new(coarse_rhs.width, coarse_rhs.height);
+    v_cycle_level(
+        &mut correction,
+        &coarse_rhs,
+        h2 * 4.0,
+        levels_below - 1,
+        settings,
+    );
+    prolongate_add(p, &correction);
+
+    weighted_jacobi(p, rhs, h2, settings.omega, settings.post_smooth);
+}
@@ -51,6 +51,108 @@ impl Texture {
             sampler,
         }
     }
+
+    // Overwrites the whole texture with tightly packed texels (row-major, top row first).
+    pub fn write_all(&self, queue: &wgpu::Queue, texels: &[u8]) {
+        let size = self.texture.size();
+        let bytes_per_texel = self
+            .texture
+            .format()
+            .block_copy_size(None)
+            .expect("write_all needs a colour format");
+        queue.write_texture(
+            self.texture.as_image_copy(),
+            texels,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width * bytes_per_texel),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+    }
+
+    // Records a copy of the whole texture into a new mappable buffer. Map it once the
+    // encoder has been submitted, then read the texels.
+    pub fn stage(&self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) -> StagedTexels {
+        let size = self.texture.size();
+        let bytes_per_texel = self
+            .texture
+            .format()
+            .block_copy_size(None)
+            .expect("stage needs a colour format");
+        let unpadded_bytes_per_row = size.width * bytes_per_texel;
+        let padded_bytes_per_row = unpadded_bytes_per_row
+            .div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: (padded_bytes_per_row * size.height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        StagedTexels {
+            buffer,
+            unpadded_bytes_per_row,
+            padded_bytes_per_row,
+        }
+    }
+
+    // Copies the whole texture into a mapped buffer and returns the texels as f32s.
+    // Blocks until the GPU is done.
+    pub fn read_back(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Vec<f32> {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Encoder"),
+        });
+        let staged = self.stage(device, &mut encoder);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        staged
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, |_| {});
+        device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .expect("GPU readback failed");
+        staged.texels()
+    }
+}
+
+// A texture copy on its way back from the GPU (Texture::stage)
+pub struct StagedTexels {
+    pub buffer: wgpu::Buffer,
+    unpadded_bytes_per_row: u32,
+    padded_bytes_per_row: u32,
+}
+
+impl StagedTexels {
+    // The texels as f32s, once the buffer is mapped. Works from any thread.
+    // GPU rows are padded to COPY_BYTES_PER_ROW_ALIGNMENT; the padding is stripped here.
+    pub fn texels(&self) -> Vec<f32> {
+        let mapped = self.buffer.slice(..).get_mapped_range();
+        let rows = mapped.len() / self.padded_bytes_per_row as usize;
+        let mut texels = Vec::with_capacity(rows * self.unpadded_bytes_per_row as usize / 4);
+        for row in mapped.chunks(self.padded_bytes_per_row as usize) {
+            let row = &row[..self.unpadded_bytes_per_row as usize];
+            texels.extend_from_slice(bytemuck::cast_slice::<u8, f32>(row));
+        }
+        drop(mapped);
+        self.buffer.unmap();
+        texels
+    }
 }
 
 pub fn create_sim_textures(
@@ -66,6 +168,7 @@ pub fn create_sim_textures(
     Texture,
     Texture,
     Texture,
+    Texture,
 ) {
     let closure = |name: &str, texture_type: wgpu::TextureFormat| {
         Texture::create_storage_texture(device, sim_width, sim_height, texture_type, Some(name))
@@ -78,6 +181,7 @@ pub fn create_sim_textures(
     let pressure_b = closure("Pressure B", wgpu::TextureFormat::R32Float);
     let divergence = closure("Pressure A", wgpu::TextureFormat::R32Float);
     let temp_density = closure("Temp Density", wgpu::TextureFormat::Rgba32Float);
+    let temp_velocity = closure("Temp Velocity", wgpu::TextureFormat::Rg32Float);
     (
         density_a,
         density_b,
@@ -87,5 +191,6 @@ pub fn create_sim_textures(
         pressure_b,
         divergence,
         temp_density,
+        temp_velocity,
     )
 }
@@ -4,6 +4,8 @@ struct AdvectionUniforms {
     height: f32,
     velocity_decay: f32,
     ink_decay: f32,
+    grain_friction: f32, // Extra drag on the paper's ridges, per second
+    boundary: vec4<u32>, // Per-edge mode, see boundary.wgsl
 };
 
 @group(0) @binding(0) var<uniform> params: AdvectionUniforms;
@@ -11,7 +13,66 @@ struct AdvectionUniforms {
 @group(0) @binding(2) var density_in: texture_2d<f32>;
 @group(0) @binding(3) var velocity_out: texture_storage_2d<rg32float, write>;
 @group(0) @binding(4) var density_out: texture_storage_2d<rgba32float, write>;
-@group(0) @binding(5) var tex_sampler: sampler;
+
+// MacCormack correction only: the forward (semi-Lagrangian) result
+@group(0) @binding(6) var velocity_hat: texture_2d<f32>;
+@group(0) @binding(7) var density_hat: texture_2d<f32>;
+
+// Paper relief (grain.rs): -1 valley .. +1 ridge
+@group(0) @binding(8) var grain: texture_2d<f32>;
+
+// Velocity decay at a cell: the global friction, plus the paper's ridges holding the liquid back
+fn velocity_decay_at(coords: vec2<i32>) -> f32 {
+    let ridge = max(textureLoad(grain, coords, 0).x, 0.0);
+    return params.velocity_decay * exp(-params.grain_friction * ridge * params.dt);
+}
+
+// False for the second pigment field (palette mode): it rides on the same velocity, which the
+// first pass has already moved, so only the ink is written.
+override WRITE_VELOCITY: bool = true;
+
+fn store_velocity(coords: vec2<i32>, velocity: vec2<f32>) {
+    if (WRITE_VELOCITY) {
+        textureStore(velocity_out, coords, vec4<f32>(velocity, 0.0, 0.0));
+    }
+}
+
+// One texel for the bilinear lookup. 'c' may be anywhere, e.g. where a backtrace left the grid.
+// CRITICAL FIX: Clamping the lookup prevents the "Streaking" artifacts from wrapping around,
+// so only PERIODIC edges wrap. Ink coming from past an OPEN edge is clean paper.
+fn field_texel(field: texture_2d<f32>, c: vec2<i32>, is_ink: bool) -> vec4<f32> {
+    let dims = vec2<i32>(textureDimensions(field));
+    let texel = boundary_texel(c, dims, params.boundary);
+    if (is_ink && texel.open_edge) {
+        return vec4<f32>(0.0);
+    }
+    return textureLoad(field, texel.coords, 0);
+}
+
+// Bilinear sample at a GRID position (cell centres are the integers).
+// Done by hand rather than with a sampler so periodic edges interpolate across the seam.
+fn sample_field(field: texture_2d<f32>, pos: vec2<f32>, is_ink: bool) -> vec4<f32> {
+    let base = floor(pos);
+    let t = pos - base;
+    let c = vec2<i32>(base);
+
+    let v00 = field_texel(field, c, is_ink);
+    let v10 = field_texel(field, c + vec2<i32>(1, 0), is_ink);
+    let v01 = field_texel(field, c + vec2<i32>(0, 1), is_ink);
+    let v11 = field_texel(field, c + vec2<i32>(1, 1), is_ink);
+    return mix(mix(v00, v10, t.x), mix(v01, v11, t.x), t.y);
+}
+
+// Obstacle cells don't flow: their velocity is 0 and whatever ink is under them stays put.
+// Returns true if 'coords' was such a cell (and has been written).
+fn hold_solid(coords: vec2<i32>) -> bool {
+    if (!is_solid(coords)) {
+        return false;
+    }
+    textureStore(density_out, coords, textureLoad(density_in, coords, 0));
+    store_velocity(coords, vec2<f32>(0.0));
+    return true;
+}
 
 @compute @workgroup_size(16, 16)
 fn main(@builtin(global_invocation_id) id: vec3<u32>) {
@@ -21,6 +82,9 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     if (coords.x >= dims.x || coords.y >= dims.y) {
         return;
     }
+    if (hold_solid(coords)) {
+        return;
+    }
 
     let dt = params.dt;
     
@@ -28,24 +92,16 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     let pos = vec2<f32>(f32(coords.x), f32(coords.y));
     
     // 2. Read the Velocity at this point
-    // We normailze UVs because sample_texture requires 0.0-1.0 range
-    let uv = (pos + 0.5) / vec2<f32>(params.width, params.height);
-    let velocity = textureSampleLevel(velocity_in, tex_sampler, uv, 0.0).xy;
+    let velocity = textureLoad(velocity_in, coords, 0).xy;
 
     // 3. Trace Backwards
     // "Where did the stuff at this pixel come from?"
     // Result = Pos - (Velocity * Time)
     let back_pos = pos - (velocity * dt);
 
-    // CRITICAL FIX: Clamp the lookup to be inside the texture!
-    // This prevents the "Streaking" artifacts from wrapping around.
-    let clamped_pos = clamp(back_pos, vec2<f32>(0.0, 0.0), vec2<f32>(params.width - 1.0, params.height - 1.0));
-
-    // 4. Sample using the CLAMPED position
-    let back_uv = (clamped_pos + 0.5) / vec2<f32>(params.width, params.height);
-    
-    let advected_density = textureSampleLevel(density_in, tex_sampler, back_uv, 0.0);
-    let advected_velocity = textureSampleLevel(velocity_in, tex_sampler, back_uv, 0.0);
+    // 4. Sample there (edges handled per boundary mode)
+    let advected_density = sample_field(density_in, back_pos, true);
+    let advected_velocity = sample_field(velocity_in, back_pos, false);
 
     // 5. Apply Specific Dissipation
     
@@ -53,9 +109,79 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     let new_density = advected_density * params.ink_decay;
     
     // Velocity uses velocity_decay (0.99 = slows down)
-    let new_velocity = advected_velocity.xy * params.velocity_decay; 
+    let new_velocity = advected_velocity.xy * velocity_decay_at(coords);
 
     // 6. Write Result
     textureStore(density_out, coords, new_density);
-    textureStore(velocity_out, coords, vec4<f32>(new_velocity, 0.0, 0.0));
+    store_velocity(coords, new_velocity);
+}
+
+// --- MACCORMACK ---
+// Step 1 is the plain semi-Lagrangian step WITHOUT dissipation (dissipation is applied once, at the end).
+@compute @workgroup_size(16, 16)
+fn predict_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(velocity_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+    if (hold_solid(coords)) { return; }
+
+    let pos = vec2<f32>(f32(coords.x), f32(coords.y));
+    let back_pos = pos - textureLoad(velocity_in, coords, 0).xy * params.dt;
+
+    textureStore(density_out, coords, sample_field(density_in, back_pos, true));
+    store_velocity(coords, sample_field(velocity_in, back_pos, false).xy);
+}
+
+// The four texels the semi-Lagrangian lookup at 'pos' interpolated between.
+// Their min / max bound the corrected value so the error term can't create new extrema.
+fn footprint_texels(field: texture_2d<f32>, pos: vec2<f32>, is_ink: bool) -> array<vec4<f32>, 4> {
+    let c = vec2<i32>(floor(pos));
+    return array<vec4<f32>, 4>(
+        field_texel(field, c, is_ink),
+        field_texel(field, c + vec2<i32>(1, 0), is_ink),
+        field_texel(field, c + vec2<i32>(0, 1), is_ink),
+        field_texel(field, c + vec2<i32>(1, 1), is_ink),
+    );
+}
+
+fn limit(value: vec4<f32>, texels: array<vec4<f32>, 4>) -> vec4<f32> {
+    let lo = min(min(texels[0], texels[1]), min(texels[2], texels[3]));
+    let hi = max(max(texels[0], texels[1]), max(texels[2], texels[3]));
+    return clamp(value, lo, hi);
+}
+
+// Step 2: trace the forward result BACK along the flow (+dt), compare with where we started,
+// and add half the round-trip error. Then clamp (limiter) and apply dissipation.
+@compute @workgroup_size(16, 16)
+fn maccormack_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(velocity_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+    if (hold_solid(coords)) { return; }
+
+    let pos = vec2<f32>(f32(coords.x), f32(coords.y));
+    let velocity = textureLoad(velocity_in, coords, 0).xy;
+    let back_pos = pos - velocity * params.dt;
+    let forward_pos = pos + velocity * params.dt;
+
+    // Ink
+    let density_n = textureLoad(density_in, coords, 0);
+    let density_fwd = textureLoad(density_hat, coords, 0);
+    let density_round_trip = sample_field(density_hat, forward_pos, true);
+    let density_corrected = limit(
+        density_fwd + 0.5 * (density_n - density_round_trip),
+        footprint_texels(density_in, back_pos, true),
+    );
+
+    // Velocity
+    let velocity_n = textureLoad(velocity_in, coords, 0);
+    let velocity_fwd = textureLoad(velocity_hat, coords, 0);
+    let velocity_round_trip = sample_field(velocity_hat, forward_pos, false);
+    let velocity_corrected = limit(
+        velocity_fwd + 0.5 * (velocity_n - velocity_round_trip),
+        footprint_texels(velocity_in, back_pos, false),
+    );
+
+    textureStore(density_out, coords, density_corrected * params.ink_decay);
+    store_velocity(coords, velocity_corrected.xy * velocity_decay_at(coords));
 }
@@ -0,0 +1,111 @@
+// --- BOUNDARY CONDITIONS ---
+// Shared by every shader that looks past the edge of the grid (prepended to its source).
+// Edges: 0 = left (x = 0), 1 = right (x = width - 1), 2 = top (y = 0), 3 = bottom (y = height - 1).
+// The mode values match BoundaryMode in gui.rs.
+const BOUNDARY_WALL: u32 = 0u;      // No-slip solid wall: velocity 0, nothing crosses it
+const BOUNDARY_FREE_SLIP: u32 = 1u; // Solid wall the fluid slides along: only the normal velocity is 0
+const BOUNDARY_PERIODIC: u32 = 2u;  // Wraps around to the opposite edge
+const BOUNDARY_OPEN: u32 = 3u;      // Outflow: pressure 0, fluid and ink leave freely
+
+struct Neighbour {
+    coords: vec2<i32>, // Already wrapped for periodic edges
+    outside: bool,     // Past a non-periodic edge: use one of the ghost_* values instead of loading
+    edge: u32,
+    mode: u32,
+}
+
+// The cell one step from 'coords' (offset is a unit step along x or y)
+fn neighbour(coords: vec2<i32>, offset: vec2<i32>, dims: vec2<i32>, modes: vec4<u32>) -> Neighbour {
+    let n = coords + offset;
+    var edge = 4u;
+    if (n.x < 0) {
+        edge = 0u;
+    } else if (n.x >= dims.x) {
+        edge = 1u;
+    } else if (n.y < 0) {
+        edge = 2u;
+    } else if (n.y >= dims.y) {
+        edge = 3u;
+    }
+    if (edge == 4u) {
+        return Neighbour(n, false, edge, BOUNDARY_WALL);
+    }
+
+    let mode = modes[edge];
+    if (mode == BOUNDARY_PERIODIC) {
+        return Neighbour((n + dims) % dims, false, edge, mode);
+    }
+    return Neighbour(n, true, edge, mode);
+}
+
+// Pressure past an edge. Walls copy the centre (zero gradient, Neumann); open edges are p = 0.
+fn ghost_pressure(n: Neighbour, centre: f32) -> f32 {
+    if (n.mode == BOUNDARY_OPEN) {
+        return 0.0;
+    }
+    return centre;
+}
+
+// Velocity past an edge
+fn ghost_velocity(n: Neighbour, centre: vec2<f32>) -> vec2<f32> {
+    switch n.mode {
+        case BOUNDARY_FREE_SLIP: {
+            // Keep the tangential part only
+            if (n.edge < 2u) {
+                return vec2<f32>(0.0, centre.y);
+            }
+            return vec2<f32>(centre.x, 0.0);
+        }
+        case BOUNDARY_OPEN: {
+            return centre;
+        }
+        default: {
+            return vec2<f32>(0.0);
+        }
+    }
+}
+
+// Ink (or any carried quantity) past an edge. Walls hold it in; open edges drain onto clean paper.
+fn ghost_ink(n: Neighbour, centre: vec4<f32>) -> vec4<f32> {
+    if (n.mode == BOUNDARY_OPEN) {
+        return vec4<f32>(0.0);
+    }
+    return centre;
+}
+
+// Any integer texel position (possibly far outside, e.g. a backtrace) -> a texel inside the grid.
+// Periodic axes wrap, everything else clamps to the edge. 'open_edge' reports whether the
+// position lies past an open edge.
+struct Texel {
+    coords: vec2<i32>,
+    open_edge: bool,
+}
+
+fn boundary_texel(c: vec2<i32>, dims: vec2<i32>, modes: vec4<u32>) -> Texel {
+    var coords = c;
+    var open_edge = false;
+
+    // X axis
+    if (c.x < 0 || c.x >= dims.x) {
+        let mode = select(modes.y, modes.x, c.x < 0);
+        if (mode == BOUNDARY_PERIODIC) {
+            coords.x = ((c.x % dims.x) + dims.x) % dims.x;
+        } else {
+            coords.x = clamp(c.x, 0, dims.x - 1);
+            open_edge = open_edge || mode == BOUNDARY_OPEN;
+        }
+    }
+
+    // Y axis
+    if (c.y < 0 || c.y >= dims.y) {
+        let mode = select(modes.w, modes.z, c.y < 0);
+        if (mode == BOUNDARY_PERIODIC) {
+            coords.y = ((c.y % dims.y) + dims.y) % dims.y;
+        } else {
+            coords.y = clamp(c.y, 0, dims.y - 1);
+            open_edge = open_edge || mode == BOUNDARY_OPEN;
+        }
+    }
+
+    return Texel(coords, open_edge);
+}
@@ -1,11 +1,16 @@
 struct BrushUniforms {
-    mouse_pos: vec2<f32>,
-    last_mouse_pos: vec2<f32>,
+    point_count: u32, // Points in 'stroke' (at least 1)
+    path_length: f32, // Length of the whole stroke, sets the strength of the push
     velocity_factor: f32,
     radius: f32,
-    smudge: f32,
-    // Shader automatically handles the padding to align the next vec4
+    tool: u32, // BrushTool in gui.rs
+    palette: u32, // 1 = the two density fields hold pigment concentrations (palette mode)
+    // Palette mode only: brush_color / brush_color2 are the target concentrations of
+    // pigments 0-3 / 4-7, and 'amount' how far the cells are pulled toward them
+    amount: f32,
     brush_color: vec4<f32>,
+    brush_color2: vec4<f32>,
+    bounds: vec4<f32>, // Around the stroke, grown by the radius: min xy, max xy
 };
 
 @group(0) @binding(0) var<uniform> brush: BrushUniforms;
@@ -13,6 +18,18 @@ struct BrushUniforms {
 @group(0) @binding(2) var density_out: texture_storage_2d<rgba32float, write>;
 @group(0) @binding(3) var velocity_in: texture_2d<f32>;
 @group(0) @binding(4) var velocity_out: texture_storage_2d<rg32float, write>;
+// Pigments 4-7 in palette mode, passed through otherwise
+@group(0) @binding(5) var density2_in: texture_2d<f32>;
+@group(0) @binding(6) var density2_out: texture_storage_2d<rgba32float, write>;
+// Paper wetness: only written under the stroke, everywhere else keeps its value
+@group(0) @binding(7) var wetness_out: texture_storage_2d<r32float, write>;
+// The brush path since the last step, in grid coordinates (stroke.rs)
+@group(0) @binding(8) var<storage, read> stroke: array<vec2<f32>>;
+
+const TOOL_PAINT: u32 = 0u;
+const TOOL_SMUDGE: u32 = 1u;
+const TOOL_OBSTACLE: u32 = 2u;
+const TOOL_ERASE_OBSTACLE: u32 = 3u;
 
 fn dist_sq_to_segment(p: vec2<f32>, p1: vec2<f32>, p2: vec2<f32>) -> f32 {
     let l2 = dot(p2 - p1, p2 - p1);
@@ -22,6 +39,39 @@ fn dist_sq_to_segment(p: vec2<f32>, p1: vec2<f32>, p2: vec2<f32>) -> f32 {
     return dot(p - projection, p - projection);
 }
 
+struct StrokeHit {
+    d2: f32,             // Squared distance to the nearest segment
+    direction: vec2<f32>, // That segment, start to end
+};
+
+// Returned for cells outside the stroke's bounds: further than any radius
+const MISS: f32 = 1e30;
+
+fn nearest_on_stroke(p: vec2<f32>) -> StrokeHit {
+    // Most of the canvas is nowhere near the stroke; don't walk the segments there
+    if (any(p < brush.bounds.xy) || any(p > brush.bounds.zw)) {
+        return StrokeHit(MISS, vec2<f32>(0.0));
+    }
+    var hit = StrokeHit(dist_sq_to_segment(p, stroke[0], stroke[0]), vec2<f32>(0.0));
+    for (var i = 1u; i < brush.point_count; i++) {
+        let d2 = dist_sq_to_segment(p, stroke[i - 1u], stroke[i]);
+        if (d2 < hit.d2) {
+            hit = StrokeHit(d2, stroke[i] - stroke[i - 1u]);
+        }
+    }
+    return hit;
+}
+
+// The push the stroke gives the liquid: along the nearest segment, as strong as the whole
+// stroke is long (a straight stroke pushes exactly like the single segment used to)
+fn stroke_velocity(hit: StrokeHit) -> vec2<f32> {
+    let len = length(hit.direction);
+    if (len == 0.0) {
+        return vec2<f32>(0.0);
+    }
+    return hit.direction / len * brush.path_length * brush.velocity_factor;
+}
+
 @compute @workgroup_size(16, 16)
 fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     let coords = vec2<i32>(id.xy);
@@ -34,22 +84,28 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     // 1. ALWAYS Read the Input (This is the Advected Result from Step 1)
     let in_density = textureLoad(density_in, coords, 0);
     let in_velocity = textureLoad(velocity_in, coords, 0);
+    let in_density2 = textureLoad(density2_in, coords, 0);
 
     // 2. Prepare variables to hold the Final Value
     var final_density = in_density;
     var final_velocity = in_velocity;
+    var final_density2 = in_density2;
 
     // 3. If inside Brush, Modify the values
     let pixel_pos = vec2<f32>(f32(coords.x), f32(coords.y));
-    let d2 = dist_sq_to_segment(pixel_pos, brush.last_mouse_pos, brush.mouse_pos);
+    let hit = nearest_on_stroke(pixel_pos);
+
+    if (hit.d2 < brush.radius * brush.radius) {
+        // The brush carries water: the paper under it is soaked again
+        textureStore(wetness_out, coords, vec4<f32>(1.0, 0.0, 0.0, 0.0));
 
-    if (d2 < brush.radius * brush.radius) {
         // MODE 1: BLENDER (Smudge)
-        if (brush.smudge > 0.5) {
+        if (brush.tool == TOOL_SMUDGE) {
             let c = textureLoad(density_in, coords, 0);
 
             // --- NEW: STRONGER BLUR ---
             var total_color = vec4<f32>(0.0);
+            var total_color2 = vec4<f32>(0.0);
             var count = 0.0;
             
             // Radius 4 means we sample a 9x9 box (81 pixels!)
@@ -64,6 +120,7 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
                     let safe_pos = clamp(neighbor_pos, vec2<i32>(0, 0), dims - vec2<i32>(1, 1));
                     
                     total_color += textureLoad(density_in, safe_pos, 0);
+                    total_color2 += textureLoad(density2_in, safe_pos, 0);
                     count += 1.0;
                 }
             }
@@ -73,12 +130,22 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
             // Mix 90% of the blurred color in (was 0.5 before)
             // This makes the smudge instant instead of gradual.
             final_density = mix(c, blurred, 0.9);
+            final_density2 = mix(in_density2, total_color2 / count, 0.9);
             // ---------------------------
 
             // CRITICAL: We still add velocity! 
-            let velocity_add = (brush.mouse_pos - brush.last_mouse_pos) * brush.velocity_factor;
+            let velocity_add = stroke_velocity(hit);
             final_velocity = vec4<f32>(final_velocity.xy + velocity_add, 0.0, 0.0);
 
+        } else if (brush.palette != 0u) {
+            // MODE 2b: PAINTER in palette mode
+            // Every concentration moves toward the selected pigment's (1 for it, 0 for the rest),
+            // so the total stays at most 1 and the other pigments get covered.
+            final_density = mix(final_density, brush.brush_color, brush.amount);
+            final_density2 = mix(final_density2, brush.brush_color2, brush.amount);
+
+            let velocity_add = stroke_velocity(hit);
+            final_velocity = vec4<f32>(final_velocity.xy + velocity_add, 0.0, 0.0);
         } else {
             // MODE 2: PAINTER (Add Ink)
             // The Alpha of the brush IS the amount we add.
@@ -95,7 +162,7 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
             final_density = vec4<f32>(mixed_rgb, new_alpha);
 
             // Add Velocity
-            let velocity_add = (brush.mouse_pos - brush.last_mouse_pos) * brush.velocity_factor; // Tweak force here
+            let velocity_add = stroke_velocity(hit); // Tweak force with velocity_factor
             final_velocity = vec4<f32>(final_velocity.xy + velocity_add, 0.0, 0.0);
         }
     }
@@ -104,4 +171,23 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     // This ensures the advection (movement/fading) is applied to the whole screen
     textureStore(density_out, coords, final_density);
     textureStore(velocity_out, coords, final_velocity);
+    textureStore(density2_out, coords, final_density2);
+}
+
+// --- OBSTACLE TOOLS ---
+// Only the cells under the stroke are written; the rest of the mask keeps its value.
+@group(0) @binding(1) var obstacles_out: texture_storage_2d<r32float, write>;
+// (The stroke is binding 8 here too)
+
+@compute @workgroup_size(16, 16)
+fn obstacle_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(obstacles_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    let pixel_pos = vec2<f32>(f32(coords.x), f32(coords.y));
+    if (nearest_on_stroke(pixel_pos).d2 >= brush.radius * brush.radius) { return; }
+
+    let solid = select(0.0, 1.0, brush.tool == TOOL_OBSTACLE);
+    textureStore(obstacles_out, coords, vec4<f32>(solid, 0.0, 0.0, 0.0));
 }
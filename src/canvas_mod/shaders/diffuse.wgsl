@@ -1,14 +1,29 @@
 struct DiffuseUniforms {
     width: f32,
     height: f32,
-    alpha: f32,        // determined by viscosity and dt
-    one_over_beta: f32 // 1 / (4 + alpha)
+    alpha: f32,        // determined by the diffusion rate (viscosity or bleed) and dt
+    one_over_beta: f32, // 1 / (4 + alpha)
+    omega: f32,         // Over-relaxation factor for the red-black solver
+    boundary: vec4<u32>, // Per-edge mode, see boundary.wgsl
 };
 
+// diffuse_rgba.wgsl or diffuse_rg.wgsl comes first and declares the output (binding 3) and
+// IS_VELOCITY: velocity and ink see different things past a wall
+
 @group(0) @binding(0) var<uniform> params: DiffuseUniforms;
 @group(0) @binding(1) var x_in: texture_2d<f32>;          // The texture we are diffusing (Density or Velocity)
 @group(0) @binding(2) var b_in: texture_2d<f32>;          // The original state (b in the Ax=b equation)
-@group(0) @binding(3) var x_out: texture_storage_2d<rgba32float, write>;
+
+fn field_neighbour(coords: vec2<i32>, offset: vec2<i32>, dims: vec2<i32>, centre: vec4<f32>) -> vec4<f32> {
+    let n = neighbour(coords, offset, dims, params.boundary);
+    if (!n.outside) {
+        return textureLoad(x_in, n.coords, 0);
+    }
+    if (IS_VELOCITY) {
+        return vec4<f32>(ghost_velocity(n, centre.xy), 0.0, 0.0);
+    }
+    return ghost_ink(n, centre);
+}
 
 @compute @workgroup_size(16, 16)
 fn main(@builtin(global_invocation_id) id: vec3<u32>) {
@@ -16,14 +31,12 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     let dims = vec2<i32>(textureDimensions(x_out));
     if (coords.x >= dims.x || coords.y >= dims.y) { return; }
 
-    // Read Neighbors (with clamping/boundary logic)
-    // ... (Use your standard boundary lookup logic here) ...
-    
+    // Read Neighbors (with boundary logic)
     let C = textureLoad(x_in, coords, 0);
-    let L = textureLoad(x_in, coords + vec2<i32>(-1, 0), 0);
-    let R = textureLoad(x_in, coords + vec2<i32>( 1, 0), 0);
-    let B = textureLoad(x_in, coords + vec2<i32>( 0,-1), 0);
-    let T = textureLoad(x_in, coords + vec2<i32>( 0, 1), 0);
+    let L = field_neighbour(coords, vec2<i32>(-1, 0), dims, C);
+    let R = field_neighbour(coords, vec2<i32>( 1, 0), dims, C);
+    let B = field_neighbour(coords, vec2<i32>( 0,-1), dims, C);
+    let T = field_neighbour(coords, vec2<i32>( 0, 1), dims, C);
 
     let bC = textureLoad(b_in, coords, 0);
 
@@ -32,3 +45,42 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
 
     textureStore(x_out, coords, newVal);
 }
+
+// Red-Black Gauss-Seidel / SOR variant. Same bindings as the Jacobi pass.
+// Updates one colour of the checkerboard and copies the other, so red-then-black ping-pong
+// is exactly one in-place Gauss-Seidel sweep.
+fn red_black_sweep(id: vec3<u32>, color: i32) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(x_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    let C = textureLoad(x_in, coords, 0);
+
+    // Not our colour: carry the value through unchanged
+    if ((coords.x + coords.y) % 2 != color) {
+        textureStore(x_out, coords, C);
+        return;
+    }
+
+    let L = field_neighbour(coords, vec2<i32>(-1, 0), dims, C);
+    let R = field_neighbour(coords, vec2<i32>( 1, 0), dims, C);
+    let B = field_neighbour(coords, vec2<i32>( 0,-1), dims, C);
+    let T = field_neighbour(coords, vec2<i32>( 0, 1), dims, C);
+
+    let bC = textureLoad(b_in, coords, 0);
+
+    let gauss_seidel = (L + R + B + T + (bC * params.alpha)) * params.one_over_beta;
+    let newVal = C + params.omega * (gauss_seidel - C);
+
+    textureStore(x_out, coords, newVal);
+}
+
+@compute @workgroup_size(16, 16)
+fn rb_red_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    red_black_sweep(id, 0);
+}
+
+@compute @workgroup_size(16, 16)
+fn rb_black_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    red_black_sweep(id, 1);
+}
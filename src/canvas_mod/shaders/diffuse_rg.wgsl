@@ -0,0 +1,3 @@
+// Output binding for diffuse.wgsl when diffusing velocity (viscosity)
+const IS_VELOCITY: bool = true;
+@group(0) @binding(3) var x_out: texture_storage_2d<rg32float, write>;
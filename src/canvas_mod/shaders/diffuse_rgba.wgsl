@@ -0,0 +1,3 @@
+// Output binding for diffuse.wgsl when diffusing ink (all four pigment channels)
+const IS_VELOCITY: bool = false;
+@group(0) @binding(3) var x_out: texture_storage_2d<rgba32float, write>;
@@ -0,0 +1,130 @@
+// Multigrid V-cycle passes for the pressure Poisson equation.
+//
+// Equation on a level with spacing h:  (pL + pR + pB + pT - 4 pC) / h^2 = rhs
+// Boundary: per edge, same rules as pressure.wgsl (see boundary.wgsl). With every edge a wall this
+// is pure Neumann (a neighbour outside the grid reads the centre pressure), which is what the
+// CPU reference implements. Obstacles (group 1, one mask per level) are Neumann walls as well;
+// the CPU reference has none.
+
+struct MultigridUniforms {
+    width: f32,
+    height: f32,
+    h2: f32,    // Grid spacing squared (4^level)
+    omega: f32, // Weighted Jacobi factor
+    boundary: vec4<u32>, // Per-edge mode, same on every level
+};
+
+@group(0) @binding(0) var<uniform> params: MultigridUniforms;
+@group(0) @binding(1) var field_a: texture_2d<f32>;
+@group(0) @binding(2) var field_b: texture_2d<f32>;
+@group(0) @binding(3) var field_out: texture_storage_2d<r32float, write>;
+
+fn field_a_neighbour(coords: vec2<i32>, offset: vec2<i32>, dims: vec2<i32>, center: f32) -> f32 {
+    let n = neighbour(coords, offset, dims, params.boundary);
+    if (n.outside) {
+        return ghost_pressure(n, center);
+    }
+    if (is_solid(n.coords)) {
+        return center;
+    }
+    return textureLoad(field_a, n.coords, 0).x;
+}
+
+// Edge rules applied per neighbour (walls fall back to the centre value)
+fn neighbour_sum(coords: vec2<i32>, dims: vec2<i32>, center: f32) -> f32 {
+    let l = field_a_neighbour(coords, vec2<i32>(-1, 0), dims, center);
+    let r = field_a_neighbour(coords, vec2<i32>( 1, 0), dims, center);
+    let b = field_a_neighbour(coords, vec2<i32>( 0,-1), dims, center);
+    let t = field_a_neighbour(coords, vec2<i32>( 0, 1), dims, center);
+    return l + r + b + t;
+}
+
+// --- SMOOTH: a = pressure, b = rhs, out = pressure ---
+@compute @workgroup_size(16, 16)
+fn smooth_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(field_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    if (is_solid(coords)) {
+        textureStore(field_out, coords, vec4<f32>(0.0));
+        return;
+    }
+
+    let pC = textureLoad(field_a, coords, 0).x;
+    let rhs = textureLoad(field_b, coords, 0).x;
+
+    let jacobi = (neighbour_sum(coords, dims, pC) - params.h2 * rhs) * 0.25;
+    let pNew = pC + params.omega * (jacobi - pC);
+
+    textureStore(field_out, coords, vec4<f32>(pNew, 0.0, 0.0, 0.0));
+}
+
+// --- RESIDUAL: a = pressure, b = rhs, out = rhs - A p ---
+@compute @workgroup_size(16, 16)
+fn residual_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(field_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    if (is_solid(coords)) {
+        textureStore(field_out, coords, vec4<f32>(0.0));
+        return;
+    }
+
+    let pC = textureLoad(field_a, coords, 0).x;
+    let rhs = textureLoad(field_b, coords, 0).x;
+
+    let laplacian = (neighbour_sum(coords, dims, pC) - 4.0 * pC) / params.h2;
+
+    textureStore(field_out, coords, vec4<f32>(rhs - laplacian, 0.0, 0.0, 0.0));
+}
+
+// --- RESTRICT: a = fine residual, out = coarse rhs ---
+// Sum of the 2x2 block / 4. Missing cells count as zero so the coarse rhs stays zero-mean.
+// Also restricts the obstacle mask (into a fraction, see obstacles.wgsl).
+@compute @workgroup_size(16, 16)
+fn restrict_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(field_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    let fine_dims = vec2<i32>(textureDimensions(field_a));
+    var sum = 0.0;
+    for (var fy = 0; fy < 2; fy++) {
+        for (var fx = 0; fx < 2; fx++) {
+            let fine = coords * 2 + vec2<i32>(fx, fy);
+            if (fine.x < fine_dims.x && fine.y < fine_dims.y) {
+                sum += textureLoad(field_a, fine, 0).x;
+            }
+        }
+    }
+
+    textureStore(field_out, coords, vec4<f32>(sum * 0.25, 0.0, 0.0, 0.0));
+}
+
+// --- PROLONG: a = fine pressure, b = coarse correction, out = fine pressure + correction ---
+@compute @workgroup_size(16, 16)
+fn prolong_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(field_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    // Bilinear, cell centred: fine cell x sits at (x + 0.5) / 2 - 0.5 on the coarse grid
+    let coarse_dims = vec2<i32>(textureDimensions(field_b));
+    let max_pos = vec2<f32>(coarse_dims - vec2<i32>(1, 1));
+    let pos = clamp((vec2<f32>(coords) + 0.5) * 0.5 - 0.5, vec2<f32>(0.0), max_pos);
+    let p0 = vec2<i32>(floor(pos));
+    let p1 = min(p0 + vec2<i32>(1, 1), coarse_dims - vec2<i32>(1, 1));
+    let t = pos - vec2<f32>(p0);
+
+    let c00 = textureLoad(field_b, p0, 0).x;
+    let c10 = textureLoad(field_b, vec2<i32>(p1.x, p0.y), 0).x;
+    let c01 = textureLoad(field_b, vec2<i32>(p0.x, p1.y), 0).x;
+    let c11 = textureLoad(field_b, p1, 0).x;
+    let correction = mix(mix(c00, c10, t.x), mix(c01, c11, t.x), t.y);
+
+    let pC = textureLoad(field_a, coords, 0).x;
+
+    textureStore(field_out, coords, vec4<f32>(pC + correction, 0.0, 0.0, 0.0));
+}
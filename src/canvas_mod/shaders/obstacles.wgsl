@@ -0,0 +1,10 @@
+// --- OBSTACLES ---
+// Paintable solid cells, prepended to every shader that has to flow around them.
+// Bound as group(1) so the same bind group serves all of their passes.
+// 1 = solid, 0 = liquid. Coarse multigrid levels hold the fraction of solid fine cells.
+@group(1) @binding(0) var obstacles: texture_2d<f32>;
+
+// 'coords' must be inside the grid (check Neighbour.outside first)
+fn is_solid(coords: vec2<i32>) -> bool {
+    return textureLoad(obstacles, coords, 0).x >= 0.5;
+}
@@ -0,0 +1,75 @@
+// --- PAPER LAYER ---
+// Pigment settles out of the moving liquid into a static paper layer, and the paper dries.
+// Wet paper keeps most of the pigment suspended; as it dries, the rest settles and stays put.
+
+struct PaperUniforms {
+    dt: f32,
+    deposit_rate: f32, // Fraction of the ink per second that settles on dry paper
+    drying_time: f32,  // Seconds for fully wet paper to dry
+    color_model: u32,  // ColorModel in gui.rs: how a settled layer combines with the paper
+    // Paper grain: how strongly the relief steers settling and drying, and how much faster
+    // pigment settles where wet paper meets dry (the dark rim of a watercolour wash)
+    granulation: f32,
+    absorption: f32,
+    edge_darkening: f32,
+};
+
+@group(0) @binding(0) var<uniform> params: PaperUniforms;
+@group(0) @binding(1) var density_in: texture_2d<f32>;
+@group(0) @binding(2) var paper_in: texture_2d<f32>;
+@group(0) @binding(3) var wetness_in: texture_2d<f32>;
+@group(0) @binding(4) var density_out: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(5) var paper_out: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(6) var wetness_out: texture_storage_2d<r32float, write>;
+@group(0) @binding(7) var grain: texture_2d<f32>; // -1 valley .. +1 ridge (grain.rs)
+
+const COLOR_MODEL_RGB: u32 = 0u;
+
+// Settling speed on fully wet paper, relative to dry paper
+const WET_SETTLING: f32 = 0.1;
+// A hard wet/dry edge has wet_edge = 0.5; this makes edge_darkening = 1 settle 5x faster there
+const EDGE_GAIN: f32 = 8.0;
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(density_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    let fluid = textureLoad(density_in, coords, 0);
+    let paper = textureLoad(paper_in, coords, 0);
+    let wetness = textureLoad(wetness_in, coords, 0).x;
+    let relief = textureLoad(grain, coords, 0).x;
+
+    // Edge of a wet area: where the wetness changes fastest (clamped at the canvas edges)
+    let wet_l = textureLoad(wetness_in, max(coords - vec2<i32>(1, 0), vec2<i32>(0)), 0).x;
+    let wet_r = textureLoad(wetness_in, min(coords + vec2<i32>(1, 0), dims - 1), 0).x;
+    let wet_b = textureLoad(wetness_in, max(coords - vec2<i32>(0, 1), vec2<i32>(0)), 0).x;
+    let wet_t = textureLoad(wetness_in, min(coords + vec2<i32>(0, 1), dims - 1), 0).x;
+    let wet_edge = length(vec2<f32>(wet_r - wet_l, wet_t - wet_b)) * 0.5;
+
+    // Fraction of the suspended ink that settles this step.
+    // Pigment collects in the valleys of the paper (granulation) and at the rim of the wash.
+    let grain_settling = max(1.0 - params.granulation * relief, 0.0);
+    let edge_settling = 1.0 + params.edge_darkening * wet_edge * EDGE_GAIN;
+    let rate = params.deposit_rate * mix(1.0, WET_SETTLING, wetness) * grain_settling * edge_settling;
+    let settled = 1.0 - exp(-rate * params.dt);
+
+    if (params.color_model == COLOR_MODEL_RGB) {
+        // RGB: the settled ink is a new layer on top of the paper ("over"), same colour as the liquid
+        let d = fluid.a * settled;
+        let a = d + paper.a * (1.0 - d);
+        let rgb = (fluid.rgb * d + paper.rgb * paper.a * (1.0 - d)) / max(a, 1e-6);
+        textureStore(paper_out, coords, vec4<f32>(rgb, a));
+        textureStore(density_out, coords, vec4<f32>(fluid.rgb, fluid.a - d));
+    } else {
+        // K/S values and pigment concentrations are amounts: they just move across
+        textureStore(paper_out, coords, paper + fluid * settled);
+        textureStore(density_out, coords, fluid * (1.0 - settled));
+    }
+
+    // The ridges give up their water first, the valleys stay wet longest
+    let drying = params.dt / params.drying_time * max(1.0 + params.absorption * relief, 0.0);
+    let dried = max(wetness - drying, 0.0);
+    textureStore(wetness_out, coords, vec4<f32>(dried, 0.0, 0.0, 0.0));
+}
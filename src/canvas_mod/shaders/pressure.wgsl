@@ -2,6 +2,8 @@ struct PressureUniforms {
     width: f32,
     height: f32,
     dt: f32,
+    omega: f32, // Over-relaxation factor for the red-black solver (1.0 = plain Gauss-Seidel)
+    boundary: vec4<u32>, // Per-edge mode, see boundary.wgsl
 };
 
 @group(0) @binding(0) var<uniform> params: PressureUniforms;
@@ -10,20 +12,37 @@ struct PressureUniforms {
 @group(0) @binding(1) var velocity_in: texture_2d<f32>;
 @group(0) @binding(2) var divergence_out: texture_storage_2d<r32float, write>;
 
+fn velocity_neighbour(coords: vec2<i32>, offset: vec2<i32>, dims: vec2<i32>, centre: vec2<f32>) -> vec2<f32> {
+    let n = neighbour(coords, offset, dims, params.boundary);
+    if (n.outside) {
+        return ghost_velocity(n, centre);
+    }
+    // Obstacles don't move: nothing flows through their faces
+    if (is_solid(n.coords)) {
+        return vec2<f32>(0.0);
+    }
+    return textureLoad(velocity_in, n.coords, 0).xy;
+}
+
 @compute @workgroup_size(16, 16)
 fn divergence_main(@builtin(global_invocation_id) id: vec3<u32>) {
     let coords = vec2<i32>(id.xy);
     let dims = vec2<i32>(textureDimensions(divergence_out));
     if (coords.x >= dims.x || coords.y >= dims.y) { return; }
 
+    if (is_solid(coords)) {
+        textureStore(divergence_out, coords, vec4<f32>(0.0));
+        return;
+    }
+
     // DIVERGENCE BOUNDARY FIX:
-    // If a neighbor is outside the screen, assume velocity is 0 (Solid Wall).
+    // If a neighbor is outside the screen, use the edge's ghost velocity (0 for a Solid Wall).
     // Otherwise, read the texture.
-    
-    let w = select(textureLoad(velocity_in, coords + vec2<i32>(-1, 0), 0).xy, vec2<f32>(0.0), coords.x == 0);
-    let e = select(textureLoad(velocity_in, coords + vec2<i32>( 1, 0), 0).xy, vec2<f32>(0.0), coords.x == dims.x - 1);
-    let s = select(textureLoad(velocity_in, coords + vec2<i32>( 0,-1), 0).xy, vec2<f32>(0.0), coords.y == 0);
-    let n = select(textureLoad(velocity_in, coords + vec2<i32>( 0, 1), 0).xy, vec2<f32>(0.0), coords.y == dims.y - 1);
+    let vC = textureLoad(velocity_in, coords, 0).xy;
+    let w = velocity_neighbour(coords, vec2<i32>(-1, 0), dims, vC);
+    let e = velocity_neighbour(coords, vec2<i32>( 1, 0), dims, vC);
+    let s = velocity_neighbour(coords, vec2<i32>( 0,-1), dims, vC);
+    let n = velocity_neighbour(coords, vec2<i32>( 0, 1), dims, vC);
 
     let div = 0.5 * (e.x - w.x + n.y - s.y);
 
@@ -35,26 +54,44 @@ fn divergence_main(@builtin(global_invocation_id) id: vec3<u32>) {
 @group(0) @binding(2) var divergence_in: texture_2d<f32>;
 @group(0) @binding(3) var pressure_out: texture_storage_2d<r32float, write>;
 
+fn pressure_neighbour(coords: vec2<i32>, offset: vec2<i32>, dims: vec2<i32>, centre: f32) -> f32 {
+    let n = neighbour(coords, offset, dims, params.boundary);
+    if (n.outside) {
+        return ghost_pressure(n, centre);
+    }
+    // Obstacles are walls too: zero pressure gradient across them (Neumann)
+    if (is_solid(n.coords)) {
+        return centre;
+    }
+    return textureLoad(pressure_in, n.coords, 0).x;
+}
+
 @compute @workgroup_size(16, 16)
 fn jacobi_main(@builtin(global_invocation_id) id: vec3<u32>) {
     let coords = vec2<i32>(id.xy);
     let dims = vec2<i32>(textureDimensions(pressure_out));
     if (coords.x >= dims.x || coords.y >= dims.y) { return; }
 
+    // Pressure inside an obstacle is never read
+    if (is_solid(coords)) {
+        textureStore(pressure_out, coords, vec4<f32>(0.0));
+        return;
+    }
+
     // Get Center Divergence
     let bC = textureLoad(divergence_in, coords, 0).x;
 
     // Get Center Pressure (needed for boundary fallback)
     let pC = textureLoad(pressure_in, coords, 0).x;
 
-    // PRESSURE BOUNDARY FIX (Pure Neumann):
+    // PRESSURE BOUNDARY FIX (Neumann at walls):
     // If neighbor is a wall, use Center Pressure (pC) instead of neighbor pressure.
     // This tells the physics "The pressure difference at the wall is zero".
-    
-    let pL = select(textureLoad(pressure_in, coords + vec2<i32>(-1, 0), 0).x, pC, coords.x == 0);
-    let pR = select(textureLoad(pressure_in, coords + vec2<i32>( 1, 0), 0).x, pC, coords.x == dims.x - 1);
-    let pB = select(textureLoad(pressure_in, coords + vec2<i32>( 0,-1), 0).x, pC, coords.y == 0);
-    let pT = select(textureLoad(pressure_in, coords + vec2<i32>( 0, 1), 0).x, pC, coords.y == dims.y - 1);
+    // Open edges hold the pressure at 0 instead; periodic edges read the opposite side.
+    let pL = pressure_neighbour(coords, vec2<i32>(-1, 0), dims, pC);
+    let pR = pressure_neighbour(coords, vec2<i32>( 1, 0), dims, pC);
+    let pB = pressure_neighbour(coords, vec2<i32>( 0,-1), dims, pC);
+    let pT = pressure_neighbour(coords, vec2<i32>( 0, 1), dims, pC);
 
     // Jacobi formula
     let pNew = (pL + pR + pB + pT - bC) * 0.25;
@@ -62,30 +99,99 @@ fn jacobi_main(@builtin(global_invocation_id) id: vec3<u32>) {
     textureStore(pressure_out, coords, vec4<f32>(pNew, 0.0, 0.0, 0.0));
 }
 
+// --- RED-BLACK GAUSS-SEIDEL / SOR (PRESSURE) PIPELINE ---
+// Same bindings as Jacobi. Each pass updates one colour of the checkerboard and copies the other,
+// so red-then-black ping-pong gives exactly the in-place Gauss-Seidel sweep.
+fn red_black_sweep(id: vec3<u32>, color: i32) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(pressure_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    let pC = textureLoad(pressure_in, coords, 0).x;
+
+    // Not our colour (or inside an obstacle): carry the value through unchanged
+    if ((coords.x + coords.y) % 2 != color || is_solid(coords)) {
+        textureStore(pressure_out, coords, vec4<f32>(pC, 0.0, 0.0, 0.0));
+        return;
+    }
+
+    let bC = textureLoad(divergence_in, coords, 0).x;
+
+    // Same boundary as Jacobi
+    let pL = pressure_neighbour(coords, vec2<i32>(-1, 0), dims, pC);
+    let pR = pressure_neighbour(coords, vec2<i32>( 1, 0), dims, pC);
+    let pB = pressure_neighbour(coords, vec2<i32>( 0,-1), dims, pC);
+    let pT = pressure_neighbour(coords, vec2<i32>( 0, 1), dims, pC);
+
+    let gauss_seidel = (pL + pR + pB + pT - bC) * 0.25;
+    let pNew = pC + params.omega * (gauss_seidel - pC);
+
+    textureStore(pressure_out, coords, vec4<f32>(pNew, 0.0, 0.0, 0.0));
+}
+
+@compute @workgroup_size(16, 16)
+fn rb_red_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    red_black_sweep(id, 0);
+}
+
+@compute @workgroup_size(16, 16)
+fn rb_black_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    red_black_sweep(id, 1);
+}
+
 // --- SUBTRACT GRADIENT PIPELINE ---
 @group(0) @binding(1) var pressure_final: texture_2d<f32>;
 @group(0) @binding(2) var velocity_old: texture_2d<f32>;
 @group(0) @binding(3) var velocity_new: texture_storage_2d<rg32float, write>;
 
+fn final_pressure_neighbour(coords: vec2<i32>, offset: vec2<i32>, dims: vec2<i32>, centre: f32) -> f32 {
+    let n = neighbour(coords, offset, dims, params.boundary);
+    if (n.outside) {
+        return ghost_pressure(n, centre);
+    }
+    if (is_solid(n.coords)) {
+        return centre;
+    }
+    return textureLoad(pressure_final, n.coords, 0).x;
+}
+
+fn side_is_solid(coords: vec2<i32>, offset: vec2<i32>, dims: vec2<i32>) -> bool {
+    let n = neighbour(coords, offset, dims, params.boundary);
+    return !n.outside && is_solid(n.coords);
+}
+
 @compute @workgroup_size(16, 16)
 fn subtract_main(@builtin(global_invocation_id) id: vec3<u32>) {
     let coords = vec2<i32>(id.xy);
     let dims = vec2<i32>(textureDimensions(velocity_new));
     if (coords.x >= dims.x || coords.y >= dims.y) { return; }
 
+    if (is_solid(coords)) {
+        textureStore(velocity_new, coords, vec4<f32>(0.0));
+        return;
+    }
+
     let pC = textureLoad(pressure_final, coords, 0).x;
 
     // GRADIENT BOUNDARY FIX:
     // Same rule: If neighbor is wall, use Center Pressure.
-    let pL = select(textureLoad(pressure_final, coords + vec2<i32>(-1, 0), 0).x, pC, coords.x == 0);
-    let pR = select(textureLoad(pressure_final, coords + vec2<i32>( 1, 0), 0).x, pC, coords.x == dims.x - 1);
-    let pB = select(textureLoad(pressure_final, coords + vec2<i32>( 0,-1), 0).x, pC, coords.y == 0);
-    let pT = select(textureLoad(pressure_final, coords + vec2<i32>( 0, 1), 0).x, pC, coords.y == dims.y - 1);
+    let pL = final_pressure_neighbour(coords, vec2<i32>(-1, 0), dims, pC);
+    let pR = final_pressure_neighbour(coords, vec2<i32>( 1, 0), dims, pC);
+    let pB = final_pressure_neighbour(coords, vec2<i32>( 0,-1), dims, pC);
+    let pT = final_pressure_neighbour(coords, vec2<i32>( 0, 1), dims, pC);
 
     let grad = vec2<f32>(pR - pL, pT - pB) * 0.5;
 
     let old_v = textureLoad(velocity_old, coords, 0).xy;
-    let new_v = old_v - grad;
+    var new_v = old_v - grad;
+
+    // No normal velocity into a neighbouring obstacle (the solver alone only gets close)
+    if (side_is_solid(coords, vec2<i32>(-1, 0), dims) || side_is_solid(coords, vec2<i32>(1, 0), dims)) {
+        new_v.x = 0.0;
+    }
+    if (side_is_solid(coords, vec2<i32>(0, -1), dims) || side_is_solid(coords, vec2<i32>(0, 1), dims)) {
+        new_v.y = 0.0;
+    }
 
     textureStore(velocity_new, coords, vec4<f32>(new_v, 0.0, 0.0));
 }
@@ -0,0 +1,72 @@
+// Two-pass reduction of a scalar field into (sum of squares, max |value|).
+// Pass 1 reduces each 16x16 tile into one partial, pass 2 reduces the partials.
+
+var<workgroup> wg_sum: array<f32, 256>;
+var<workgroup> wg_max: array<f32, 256>;
+
+// Tree reduction over the 256 workgroup slots. Result ends in slot 0.
+fn reduce_workgroup(lid: u32) {
+    for (var stride = 128u; stride > 0u; stride = stride >> 1u) {
+        if (lid < stride) {
+            wg_sum[lid] = wg_sum[lid] + wg_sum[lid + stride];
+            wg_max[lid] = max(wg_max[lid], wg_max[lid + stride]);
+        }
+        workgroupBarrier();
+    }
+}
+
+// --- PASS 1: FIELD -> PARTIALS ---
+@group(0) @binding(0) var field_in: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> partials_out: array<vec2<f32>>;
+
+@compute @workgroup_size(16, 16)
+fn partial_main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) lid: u32,
+    @builtin(workgroup_id) wid: vec3<u32>,
+    @builtin(num_workgroups) num_groups: vec3<u32>,
+) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(field_in));
+
+    // No early return: every invocation must reach the barriers
+    var value = 0.0;
+    if (coords.x < dims.x && coords.y < dims.y) {
+        value = textureLoad(field_in, coords, 0).x;
+    }
+    wg_sum[lid] = value * value;
+    wg_max[lid] = abs(value);
+    workgroupBarrier();
+
+    reduce_workgroup(lid);
+
+    if (lid == 0u) {
+        partials_out[wid.y * num_groups.x + wid.x] = vec2<f32>(wg_sum[0], wg_max[0]);
+    }
+}
+
+// --- PASS 2: PARTIALS -> RESULT ---
+@group(0) @binding(0) var<storage, read> partials_in: array<vec2<f32>>;
+@group(0) @binding(1) var<storage, read_write> result_out: vec4<f32>;
+
+@compute @workgroup_size(256)
+fn final_main(@builtin(local_invocation_index) lid: u32) {
+    let count = arrayLength(&partials_in);
+
+    var sum = 0.0;
+    var max_value = 0.0;
+    for (var i = lid; i < count; i += 256u) {
+        let partial = partials_in[i];
+        sum += partial.x;
+        max_value = max(max_value, partial.y);
+    }
+    wg_sum[lid] = sum;
+    wg_max[lid] = max_value;
+    workgroupBarrier();
+
+    reduce_workgroup(lid);
+
+    if (lid == 0u) {
+        result_out = vec4<f32>(wg_sum[0], wg_max[0], 0.0, 0.0);
+    }
+}
@@ -0,0 +1,68 @@
+struct ResampleUniforms {
+    velocity_scale: vec2<f32>, // new_size / old_size, so velocities keep their on-canvas speed
+};
+
+@group(0) @binding(0) var<uniform> params: ResampleUniforms;
+@group(0) @binding(1) var source: texture_2d<f32>;
+@group(0) @binding(2) var source_sampler: sampler;
+
+// --- DENSITY (RGBA) ---
+@group(0) @binding(3) var density_out: texture_storage_2d<rgba32float, write>;
+
+@compute @workgroup_size(16, 16)
+fn resample_density_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(density_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    // Sample the OLD grid at the same normalized position (bilinear)
+    let uv = (vec2<f32>(coords) + 0.5) / vec2<f32>(dims);
+    let value = textureSampleLevel(source, source_sampler, uv, 0.0);
+
+    textureStore(density_out, coords, value);
+}
+
+// --- VELOCITY (RG) ---
+@group(0) @binding(3) var velocity_out: texture_storage_2d<rg32float, write>;
+
+@compute @workgroup_size(16, 16)
+fn resample_velocity_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(velocity_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    let uv = (vec2<f32>(coords) + 0.5) / vec2<f32>(dims);
+    let value = textureSampleLevel(source, source_sampler, uv, 0.0).xy * params.velocity_scale;
+
+    textureStore(velocity_out, coords, vec4<f32>(value, 0.0, 0.0));
+}
+
+// --- MASK (R, 0 or 1) ---
+@group(0) @binding(3) var mask_out: texture_storage_2d<r32float, write>;
+
+@compute @workgroup_size(16, 16)
+fn resample_mask_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(mask_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    // Threshold the bilinear value so the edges stay hard
+    let uv = (vec2<f32>(coords) + 0.5) / vec2<f32>(dims);
+    let value = step(0.5, textureSampleLevel(source, source_sampler, uv, 0.0).x);
+
+    textureStore(mask_out, coords, vec4<f32>(value, 0.0, 0.0, 0.0));
+}
+
+// --- SCALAR (R, bilinear) ---
+// Same output binding as the mask, without the threshold (e.g. the paper grain)
+@compute @workgroup_size(16, 16)
+fn resample_scalar_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(mask_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    let uv = (vec2<f32>(coords) + 0.5) / vec2<f32>(dims);
+    let value = textureSampleLevel(source, source_sampler, uv, 0.0).x;
+
+    textureStore(mask_out, coords, vec4<f32>(value, 0.0, 0.0, 0.0));
+}
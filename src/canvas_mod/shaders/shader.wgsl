@@ -1,8 +1,23 @@
 struct ViewUniforms {
     screen_size: vec2<f32>,
     canvas_size: vec2<f32>,
-    pan: vec2<f32>,
-    zoom: f32,
+    pan: vec2<f32>, // Canvas centre relative to the screen centre, screen pixels (y down)
+    zoom: f32, // Screen pixels per cell
+    show_obstacles: u32, // 1 = tint the solid cells
+    show_mask_preview: u32, // 1 = outline an imported mask that hasn't been applied yet
+    color_model: u32, // ColorModel in gui.rs: what the density textures hold
+    grain_shading: f32, // How dark the valleys of the paper grain look
+    rotation: f32, // Radians, clockwise on screen, about the canvas centre
+    paper_color: vec4<f32>, // RGB used
+};
+
+const COLOR_MODEL_RGB: u32 = 0u;
+const COLOR_MODEL_PIGMENT: u32 = 1u; // Kubelka-Munk K/S per channel (canvas_mod/pigment.rs)
+const COLOR_MODEL_PALETTE: u32 = 2u; // Concentrations of the 8 palette pigments
+
+struct PaletteUniforms {
+    absorption: array<vec4<f32>, 8>,
+    scattering: array<vec4<f32>, 8>,
 };
 
 struct VertexInput {
@@ -21,16 +36,18 @@ struct VertexOutput {
 fn vs_main(model: VertexInput) -> VertexOutput {
     var out: VertexOutput;
 
-    // Calculate the Ratio (Target Pixels / Screen Pixels)
-    // Example: 1920 / 3840 = 0.5 (Take up half the screen)
-    let size_ratio = view.canvas_size / view.screen_size;
+    // The corner in cells from the canvas centre. Screen and grid point y down, clip space up.
+    let flip_y = vec2<f32>(1.0, -1.0);
+    let cell = model.position.xy * flip_y * view.canvas_size * 0.5;
 
-    // Apply Zoom
-    let final_scale = size_ratio * view.zoom;
+    // Rotate, then Zoom: pixels from the canvas centre
+    let c = cos(view.rotation);
+    let s = sin(view.rotation);
+    let turned = vec2<f32>(c * cell.x - s * cell.y, s * cell.x + c * cell.y);
+    let pixels = turned * view.zoom + view.pan;
 
-    // Apply to Vertex Position
-    // We only scale X and Y. We add Pan here too for the future.
-    let pos_xy = (model.position.xy * final_scale) + view.pan;
+    // Pixels from the screen centre -> clip space (2 units across the screen)
+    let pos_xy = pixels * 2.0 / view.screen_size * flip_y;
 
     out.clip_position = vec4<f32>(pos_xy, 0.0, 1.0);
     out.tex_coords = model.tex_coords;
@@ -40,6 +57,38 @@ fn vs_main(model: VertexInput) -> VertexOutput {
 // Fragment Shader
 @group(0) @binding(0) var density_texture: texture_2d<f32>;
 @group(0) @binding(1) var density_sampler: sampler;
+@group(0) @binding(3) var obstacle_texture: texture_2d<f32>;
+@group(0) @binding(4) var mask_preview_texture: texture_2d<f32>;
+@group(0) @binding(5) var density2_texture: texture_2d<f32>;
+@group(0) @binding(6) var<uniform> palette: PaletteUniforms;
+@group(0) @binding(7) var paper_texture: texture_2d<f32>; // Ink settled into the paper
+@group(0) @binding(8) var paper2_texture: texture_2d<f32>;
+@group(0) @binding(9) var grain_texture: texture_2d<f32>; // Paper relief, -1 valley .. +1 ridge
+
+// Kubelka-Munk: reflectance of an opaque layer with the given K/S ratio.
+fn km_reflectance(ks: vec3<f32>) -> vec3<f32> {
+    let k = max(ks, vec3<f32>(0.0));
+    return 1.0 + k - sqrt(k * k + 2.0 * k);
+}
+
+// Scattering of the bare paper where the concentrations add up to less than 1
+const PAPER_SCATTERING: f32 = 1.0;
+
+// Mixes the palette pigments by concentration (K and S add up separately, then K/S -> R).
+fn palette_reflectance(low: vec4<f32>, high: vec4<f32>) -> vec3<f32> {
+    let c = array<f32, 8>(low.x, low.y, low.z, low.w, high.x, high.y, high.z, high.w);
+    var k = vec3<f32>(0.0);
+    var s = vec3<f32>(0.0);
+    var total = 0.0;
+    for (var i = 0; i < 8; i++) {
+        let amount = max(c[i], 0.0);
+        k += amount * palette.absorption[i].rgb;
+        s += amount * palette.scattering[i].rgb;
+        total += amount;
+    }
+    let paper = max(1.0 - total, 0.0) * PAPER_SCATTERING;
+    return km_reflectance(k / (s + paper));
+}
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
@@ -51,10 +100,37 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // A = How much ink is there
     let density = fluid_data.a;
 
-    let paper_color = vec3<f32>(1.0, 1.0, 1.0); // White paper
+    // Paper colour, shaded by the grain
+    let relief = textureSample(grain_texture, density_sampler, in.tex_coords).x;
+    let paper_color = view.paper_color.rgb * (1.0 + view.grain_shading * 0.5 * min(relief, 0.0));
+    let settled = textureSample(paper_texture, density_sampler, in.tex_coords);
+
+    // Blend the settled ink onto the paper, then the liquid ink over that
+    let stained_paper = mix(paper_color, settled.rgb, settled.a);
+    var final_color = mix(stained_paper, ink_color, density);
+
+    // Pigment mode: RGB are K/S values (0 = clean paper), turned into reflectance.
+    // Settled and suspended pigment simply add up.
+    if (view.color_model == COLOR_MODEL_PIGMENT) {
+        final_color = paper_color * km_reflectance(fluid_data.rgb + settled.rgb);
+    }
+    if (view.color_model == COLOR_MODEL_PALETTE) {
+        let fluid_data2 = textureSample(density2_texture, density_sampler, in.tex_coords);
+        let settled2 = textureSample(paper2_texture, density_sampler, in.tex_coords);
+        final_color = paper_color * palette_reflectance(fluid_data + settled, fluid_data2 + settled2);
+    }
 
-    // Blend Ink onto Paper 
-    let final_color = mix(paper_color, ink_color, density);
+    // Obstacle overlay (view only, not part of the exported image)
+    if (view.show_obstacles != 0u) {
+        let solid = textureSample(obstacle_texture, density_sampler, in.tex_coords).x;
+        let wall_color = vec3<f32>(0.35, 0.45, 0.6);
+        final_color = mix(final_color, wall_color, solid * 0.7);
+    }
+    if (view.show_mask_preview != 0u) {
+        let preview = textureSample(mask_preview_texture, density_sampler, in.tex_coords).x;
+        let preview_color = vec3<f32>(1.0, 0.5, 0.1);
+        final_color = mix(final_color, preview_color, preview * 0.5);
+    }
 
     return vec4<f32>(final_color, 1.0);
 }
@@ -0,0 +1,78 @@
+struct VorticityUniforms {
+    strength: f32, // Confinement epsilon (0 = off)
+    dt: f32,
+    boundary: vec4<u32>, // Per-edge mode, see boundary.wgsl
+};
+
+@group(0) @binding(0) var<uniform> params: VorticityUniforms;
+
+// --- CURL PIPELINE ---
+@group(0) @binding(1) var velocity_in: texture_2d<f32>;
+@group(0) @binding(2) var curl_out: texture_storage_2d<r32float, write>;
+
+fn velocity_neighbour(coords: vec2<i32>, offset: vec2<i32>, dims: vec2<i32>, centre: vec2<f32>) -> vec2<f32> {
+    let n = neighbour(coords, offset, dims, params.boundary);
+    if (n.outside) {
+        return ghost_velocity(n, centre);
+    }
+    return textureLoad(velocity_in, n.coords, 0).xy;
+}
+
+@compute @workgroup_size(16, 16)
+fn curl_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(curl_out));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    // Same edge rules as the divergence (velocity outside a wall is 0)
+    let vC = textureLoad(velocity_in, coords, 0).xy;
+    let w = velocity_neighbour(coords, vec2<i32>(-1, 0), dims, vC);
+    let e = velocity_neighbour(coords, vec2<i32>( 1, 0), dims, vC);
+    let s = velocity_neighbour(coords, vec2<i32>( 0,-1), dims, vC);
+    let n = velocity_neighbour(coords, vec2<i32>( 0, 1), dims, vC);
+
+    // 2D curl is a scalar: dVy/dx - dVx/dy
+    let curl = 0.5 * ((e.y - w.y) - (n.x - s.x));
+
+    textureStore(curl_out, coords, vec4<f32>(curl, 0.0, 0.0, 0.0));
+}
+
+// --- CONFINEMENT PIPELINE ---
+@group(0) @binding(1) var velocity_old: texture_2d<f32>;
+@group(0) @binding(2) var curl_in: texture_2d<f32>;
+@group(0) @binding(3) var velocity_new: texture_storage_2d<rg32float, write>;
+
+fn curl_neighbour(coords: vec2<i32>, offset: vec2<i32>, dims: vec2<i32>, centre: f32) -> f32 {
+    let n = neighbour(coords, offset, dims, params.boundary);
+    if (n.outside) {
+        return centre;
+    }
+    return textureLoad(curl_in, n.coords, 0).x;
+}
+
+@compute @workgroup_size(16, 16)
+fn confine_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let dims = vec2<i32>(textureDimensions(velocity_new));
+    if (coords.x >= dims.x || coords.y >= dims.y) { return; }
+
+    let cC = textureLoad(curl_in, coords, 0).x;
+
+    // Neighbour past a wall or open edge: use the centre (no gradient across it)
+    let cL = curl_neighbour(coords, vec2<i32>(-1, 0), dims, cC);
+    let cR = curl_neighbour(coords, vec2<i32>( 1, 0), dims, cC);
+    let cB = curl_neighbour(coords, vec2<i32>( 0,-1), dims, cC);
+    let cT = curl_neighbour(coords, vec2<i32>( 0, 1), dims, cC);
+
+    // N points from weak towards strong vortices
+    let eta = 0.5 * vec2<f32>(abs(cR) - abs(cL), abs(cT) - abs(cB));
+    let N = eta / (length(eta) + 1e-5);
+
+    // f = strength * (N x curl), with the curl along +z
+    let force = params.strength * vec2<f32>(N.y * cC, -N.x * cC);
+
+    let old_v = textureLoad(velocity_old, coords, 0).xy;
+    let new_v = old_v + force * params.dt;
+
+    textureStore(velocity_new, coords, vec4<f32>(new_v, 0.0, 0.0));
+}
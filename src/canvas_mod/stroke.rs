@@ -0,0 +1,81 @@
+// --- STROKE PATH ---
+// The brush path of one sim step as a polyline in GRID coordinates: every cursor sample since
+// the last step, with a Catmull-Rom spline through them so fast curves don't turn into chords.
+// The brush shader stamps the whole polyline in one dispatch (brush.wgsl).
+
+// Capacity of the GPU stroke buffer. The brush tests every cell near the stroke against
+// every segment, so this also bounds the cost of a step.
+pub const MAX_STROKE_POINTS: usize = 256;
+
+// Spline points are placed about this many cells apart, at most SUBDIVISIONS per sample
+const SPLINE_SPACING: f32 = 2.0;
+const MAX_SUBDIVISIONS: usize = 8;
+
+// Uniform Catmull-Rom between p1 and p2 at t in [0, 1]
+fn catmull_rom(p0: [f32; 2], p1: [f32; 2], p2: [f32; 2], p3: [f32; 2], t: f32) -> [f32; 2] {
+    let (t2, t3) = (t * t, t * t * t);
+    let axis = |i: usize| {
+        0.5 * (2.0 * p1[i]
+            + (p2[i] - p0[i]) * t
+            + (2.0 * p0[i] - 5.0 * p1[i] + 4.0 * p2[i] - p3[i]) * t2
+            + (3.0 * p1[i] - p0[i] - 3.0 * p2[i] + p3[i]) * t3)
+    };
+    [axis(0), axis(1)]
+}
+
+// Interpolates the samples (the first one is where the previous step's stroke ended) and
+// thins the result to MAX_STROKE_POINTS. Both ends are kept exactly.
+pub fn stroke_path(samples: &[[f32; 2]]) -> Vec<[f32; 2]> {
+    let Some(&first) = samples.first() else {
+        return Vec::new();
+    };
+    let mut path = vec![first];
+    let last = samples.len() - 1;
+    for i in 0..last {
+        // The end samples stand in for the missing neighbours
+        let p0 = samples[i.saturating_sub(1)];
+        let (p1, p2) = (samples[i], samples[i + 1]);
+        let p3 = samples[(i + 2).min(last)];
+
+        let length = (p2[0] - p1[0]).hypot(p2[1] - p1[1]);
+        let pieces = ((length / SPLINE_SPACING).ceil() as usize).clamp(1, MAX_SUBDIVISIONS);
+        for piece in 1..=pieces {
+            path.push(catmull_rom(p0, p1, p2, p3, piece as f32 / pieces as f32));
+        }
+    }
+
+    if path.len() > MAX_STROKE_POINTS {
+        let stride = (path.len() - 1) as f32 / (MAX_STROKE_POINTS - 1) as f32;
+        path = (0..MAX_STROKE_POINTS)
+            .map(|i| path[((i as f32 * stride).round() as usize).min(path.len() - 1)])
+            .collect();
+    }
+    path
+}
+
+// Box around the polyline grown by the brush radius: [min_x, min_y, max_x, max_y].
+// Cells outside it can't be under the brush, so the shader skips them.
+pub fn stroke_bounds(path: &[[f32; 2]], radius: f32) -> [f32; 4] {
+    let mut bounds = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+    for point in path {
+        bounds = [
+            bounds[0].min(point[0]),
+            bounds[1].min(point[1]),
+            bounds[2].max(point[0]),
+            bounds[3].max(point[1]),
+        ];
+    }
+    [
+        bounds[0] - radius,
+        bounds[1] - radius,
+        bounds[2] + radius,
+        bounds[3] + radius,
+    ]
+}
+
+// Total length of the polyline, in cells
+pub fn path_length(path: &[[f32; 2]]) -> f32 {
+    path.windows(2)
+        .map(|pair| (pair[1][0] - pair[0][0]).hypot(pair[1][1] - pair[0][1]))
+        .sum()
+}
@@ -0,0 +1,109 @@
+// --- VIEW TRANSFORM ---
+// Where the canvas sits on the screen, and the ONE mapping between screen pixels and grid cells.
+// The vertex shader (shader.wgsl) places the quad with it and the brush maps the cursor with it,
+// so paint lands exactly under the cursor. Keep vs_main in sync with grid_to_screen.
+// Both spaces have y pointing down. At zoom 1 a cell covers one screen pixel.
+// The canvas turns about its own centre; a positive angle turns it clockwise on screen.
+
+pub const MIN_ZOOM: f32 = 0.1;
+pub const MAX_ZOOM: f32 = 20.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ViewTransform {
+    pub pan: [f32; 2], // Canvas centre relative to the screen centre, in screen pixels
+    pub zoom: f32,     // Screen pixels per cell
+    pub rotation: f32, // Radians, in (-PI, PI]
+}
+
+impl Default for ViewTransform {
+    fn default() -> Self {
+        Self {
+            pan: [0.0, 0.0],
+            zoom: 1.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+fn centre(size: (u32, u32)) -> [f32; 2] {
+    [size.0 as f32 / 2.0, size.1 as f32 / 2.0]
+}
+
+fn rotate(v: [f32; 2], angle: f32) -> [f32; 2] {
+    let (sin, cos) = angle.sin_cos();
+    [cos * v[0] - sin * v[1], sin * v[0] + cos * v[1]]
+}
+
+impl ViewTransform {
+    // Centres the canvas at the largest zoom that shows all of it. Keeps the rotation:
+    // a turned canvas is fitted by its bounding box.
+    pub fn fit(&mut self, screen_size: (u32, u32), grid_size: (u32, u32)) {
+        let (sin, cos) = (self.rotation.sin().abs(), self.rotation.cos().abs());
+        let (width, height) = (grid_size.0.max(1) as f32, grid_size.1.max(1) as f32);
+        let zoom_x = screen_size.0 as f32 / (cos * width + sin * height);
+        let zoom_y = screen_size.1 as f32 / (sin * width + cos * height);
+        self.pan = [0.0, 0.0];
+        self.zoom = zoom_x.min(zoom_y).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    pub fn screen_to_grid(
+        &self,
+        screen_pos: [f32; 2],
+        screen_size: (u32, u32),
+        grid_size: (u32, u32),
+    ) -> [f32; 2] {
+        let (screen_centre, grid_centre) = (centre(screen_size), centre(grid_size));
+        let offset = rotate(
+            [
+                screen_pos[0] - screen_centre[0] - self.pan[0],
+                screen_pos[1] - screen_centre[1] - self.pan[1],
+            ],
+            -self.rotation,
+        );
+        [
+            grid_centre[0] + offset[0] / self.zoom,
+            grid_centre[1] + offset[1] / self.zoom,
+        ]
+    }
+
+    pub fn grid_to_screen(
+        &self,
+        grid_pos: [f32; 2],
+        screen_size: (u32, u32),
+        grid_size: (u32, u32),
+    ) -> [f32; 2] {
+        let (screen_centre, grid_centre) = (centre(screen_size), centre(grid_size));
+        let offset = rotate(
+            [grid_pos[0] - grid_centre[0], grid_pos[1] - grid_centre[1]],
+            self.rotation,
+        );
+        [
+            screen_centre[0] + self.pan[0] + offset[0] * self.zoom,
+            screen_centre[1] + self.pan[1] + offset[1] * self.zoom,
+        ]
+    }
+
+    // Moves the canvas with the cursor (screen pixels)
+    pub fn pan_by(&mut self, delta: [f32; 2]) {
+        self.pan[0] += delta[0];
+        self.pan[1] += delta[1];
+    }
+
+    // Turns the canvas about its centre, wrapping the angle into (-PI, PI]
+    pub fn rotate_by(&mut self, angle: f32) {
+        use std::f32::consts::{PI, TAU};
+        self.rotation = PI - (PI - self.rotation - angle).rem_euclid(TAU);
+    }
+
+    // Multiplies the zoom by 'factor', keeping whatever is under 'anchor' (screen pixels) in place
+    pub fn zoom_at(&mut self, anchor: [f32; 2], factor: f32, screen_size: (u32, u32)) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let scale = zoom / self.zoom;
+        let screen_centre = centre(screen_size);
+        for axis in 0..2 {
+            let from_centre = anchor[axis] - screen_centre[axis];
+            self.pan[axis] = from_centre - (from_centre - self.pan[axis]) * scale;
+        }
+        self.zoom = zoom;
+    }
+}
@@ -1,44 +1,271 @@
+use crate::canvas_mod::divergence_monitor::DivergenceStats;
+use crate::canvas_mod::pigment::PALETTE;
+use crate::canvas_mod::view::{MAX_ZOOM, MIN_ZOOM, ViewTransform};
+use crate::io_mod::import::MaskPlacement;
 use egui::Context;
 use egui_wgpu::{Renderer, RendererOptions}; // Import RendererOptions
 use egui_winit::State as EguiWinitState;
+use std::path::PathBuf;
 use wgpu::{Device, Queue, TextureFormat};
 use winit::{event::WindowEvent, window::Window};
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PressureSolver {
+    Jacobi,
+    RedBlackSor,
+    Multigrid,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiffuseSolver {
+    Jacobi,
+    RedBlackSor,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AdvectionScheme {
+    SemiLagrangian, // Soft: blurs a little every step
+    MacCormack,     // Sharp: error-corrected, clamped so it can't overshoot
+}
+
+// How the brush path is smoothed (stabilizer.rs)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StabilizerMode {
+    Off,
+    LazyMouse, // Pulled string: the brush trails the mouse at a fixed distance
+    Smooth,    // Exponential: the brush eases toward the mouse
+}
+
+// What the density field stores, and so how colours mix.
+// The values match the COLOR_MODEL_* constants in shader.wgsl.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorModel {
+    Rgb,     // Display colour + amount: colours blend like light
+    Pigment, // Kubelka–Munk K/S + concentration: colours mix like paint (canvas_mod/pigment.rs)
+    Palette, // Concentrations of the 8 named pigments in PALETTE, over both density fields
+}
+
+// What the left mouse button does. The values match the TOOL_* constants in brush.wgsl.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BrushTool {
+    Paint,     // Adds ink and pushes the liquid
+    Smudge,    // Blends the ink under the brush, still pushes the liquid
+    Wall,      // Paints solid obstacles the liquid flows around
+    EraseWall, // Removes obstacles
+}
+
+impl BrushTool {
+    // The wall tools only write the obstacle mask; ink and velocity are left alone
+    pub fn edits_walls(self) -> bool {
+        matches!(self, BrushTool::Wall | BrushTool::EraseWall)
+    }
+}
+
+// What happens at one edge of the canvas. The values match the constants in boundary.wgsl.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BoundaryMode {
+    Wall,     // Solid: no flow through or along the edge
+    FreeSlip, // Solid, but the liquid slides along it
+    Periodic, // Wraps around to the opposite edge (always set in pairs)
+    Open,     // Liquid and ink leave freely
+}
+
+impl BoundaryMode {
+    pub const ALL: [BoundaryMode; 4] = [
+        BoundaryMode::Wall,
+        BoundaryMode::FreeSlip,
+        BoundaryMode::Periodic,
+        BoundaryMode::Open,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BoundaryMode::Wall => "Wall",
+            BoundaryMode::FreeSlip => "Free-slip",
+            BoundaryMode::Periodic => "Periodic",
+            BoundaryMode::Open => "Open",
+        }
+    }
+}
+
+// Canvas sizes the panel (and a project file) may ask for, in cells per side
+pub const MIN_CANVAS_SIZE: u32 = 16;
+pub const MAX_CANVAS_SIZE: u32 = 8192;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct GuiParams {
-    pub zoom_level: f32,
+    pub view: ViewTransform, // Pan, zoom and rotation (the wheel, drags and keys edit it too)
     pub brush_size: f32,
     pub canvas_width: u32,
     pub canvas_height: u32,
     pub brush_color: [f32; 4],
+    pub color_model: ColorModel,
+    pub pigment: usize, // Palette mode: index into PALETTE the brush deposits
     pub velocity_decay: f32,
     pub ink_decay: f32,
     pub velocity_factor: f32,
-    pub viscosity: f32,
-    pub smudge: bool,
+    pub viscosity: f32,    // Velocity diffusion (resistance to shear)
+    pub ink_bleed: f32,    // Ink diffusion
+    pub deposit_rate: f32, // How fast ink settles into the paper (per second, on dry paper)
+    pub drying_time: f32,  // Seconds for a stroke's wetness to evaporate
+    // Paper grain (canvas_mod/grain.rs). None of these do anything on flat paper.
+    pub paper_color: [f32; 3],
+    pub grain_scale: f32,      // Size of the largest generated bumps, in cells
+    pub grain_shading: f32,    // How dark the valleys look
+    pub granulation: f32,      // Pigment collects in the valleys
+    pub grain_absorption: f32, // Ridges dry first
+    pub grain_friction: f32,   // Ridges slow the liquid down
+    pub edge_darkening: f32,   // Pigment collects at the rim of a wet area
+    pub tool: BrushTool,
+    pub stabilizer: StabilizerMode,
+    pub stabilizer_strength: f32, // 0 = raw mouse, 1 = strongest
+    pub show_obstacles: bool,
+    pub advection: AdvectionScheme,
+    pub vorticity: f32,              // Vorticity confinement strength
+    pub boundary: [BoundaryMode; 4], // Left, right, top, bottom
+    pub time_scale: f32,             // Simulated seconds per real second
+    pub paused: bool,
+    pub pressure_solver: PressureSolver,
+    pub pressure_iterations: u32,
+    pub multigrid_cycles: u32,
+    // Iterate toward a divergence tolerance instead of always running the full count
+    pub adaptive_pressure: bool,
+    pub divergence_tolerance: f32,
+    pub diffuse_solver: DiffuseSolver,
+    pub diffuse_iterations: u32,
+    pub sor_omega: f32,
+    pub undo_budget_mb: u32,    // GPU memory the undo snapshots may use
+    pub autosave_interval: f32, // Seconds between crash-recovery saves (recovery.rs), 0 = off
 }
 
 impl Default for GuiParams {
     fn default() -> Self {
         Self {
-            zoom_level: 1.0,
+            view: ViewTransform::default(),
             brush_size: 20.0,
             canvas_width: 1920,
             canvas_height: 1080,
             brush_color: [0.0, 0.0, 0.0, 1.0],
+            color_model: ColorModel::Rgb,
+            pigment: 0,
             velocity_decay: 1.0,
             ink_decay: 1.0,
             velocity_factor: 1.0,
-            viscosity: 1.0,
-            smudge: false,
+            viscosity: 0.0,
+            ink_bleed: 1.0,
+            deposit_rate: 0.0,
+            drying_time: 5.0,
+            paper_color: [1.0, 1.0, 1.0],
+            grain_scale: 24.0,
+            grain_shading: 0.5,
+            granulation: 0.6,
+            grain_absorption: 0.5,
+            grain_friction: 5.0,
+            edge_darkening: 0.5,
+            tool: BrushTool::Paint,
+            stabilizer: StabilizerMode::Off,
+            stabilizer_strength: 0.3,
+            show_obstacles: true,
+            advection: AdvectionScheme::SemiLagrangian,
+            vorticity: 0.0,
+            boundary: [BoundaryMode::Wall; 4],
+            time_scale: 1.0,
+            paused: false,
+            pressure_solver: PressureSolver::Jacobi,
+            pressure_iterations: 40,
+            multigrid_cycles: 2,
+            adaptive_pressure: false,
+            divergence_tolerance: 0.01,
+            diffuse_solver: DiffuseSolver::Jacobi,
+            diffuse_iterations: 50,
+            sor_omega: 1.8,
+            undo_budget_mb: 512,
+            autosave_interval: 60.0,
         }
     }
 }
 
+impl GuiParams {
+    // Per-edge modes as the shaders' uniforms expect them
+    pub fn boundary_modes(&self) -> [u32; 4] {
+        self.boundary.map(|mode| mode as u32)
+    }
+}
+
+// One-shot file actions requested from the panel. State consumes them after the frame is submitted.
+pub enum FileRequest {
+    SavePng(PathBuf),
+    SaveRawFields(PathBuf),
+    ImportImage { path: PathBuf, seed_velocity: bool },
+    // Loads an obstacle mask for placement; nothing changes until it is applied
+    ImportMask(PathBuf),
+    // Loads a height map as the paper grain
+    ImportGrain(PathBuf),
+    // The whole live painting and its settings (io_mod/project.rs)
+    SaveProject(PathBuf),
+    OpenProject(PathBuf),
+}
+
+// One-shot changes to the paper grain
+pub enum GrainAction {
+    Generate, // New procedural grain at params.grain_scale
+    Flat,     // Remove the grain
+}
+
+// One-shot steps through the undo history (canvas_mod/history.rs)
+pub enum HistoryAction {
+    Undo,
+    Redo,
+}
+
+// The answer to "Restore previous session?" after a crash
+pub enum RecoveryAction {
+    Restore,
+    Discard,
+}
+
+// What to do with an imported mask once it is in place
+pub enum MaskAction {
+    Apply, // Add it to the walls
+    Cancel,
+}
+
 pub struct Gui {
     pub context: Context,
     state: EguiWinitState,
     renderer: Renderer,
     pub params: GuiParams,
+    pub export_path: String,
+    pub import_path: String,
+    pub import_seed_velocity: bool,
+    pub file_request: Option<FileRequest>,
+    // Imported obstacle mask: State sets 'mask_pending' while one is waiting to be placed
+    pub mask_pending: bool,
+    pub mask_placement: MaskPlacement,
+    pub mask_action: Option<MaskAction>,
+    // One-shot: advance a paused sim by a single step
+    pub step_requested: bool,
+    // One-shot: remove every painted wall
+    pub clear_walls_requested: bool,
+    // One-shot: zoom and centre the canvas to fill the window
+    pub fit_requested: bool,
+    pub grain_action: Option<GrainAction>,
+    pub grain_seed: u32, // Bumped on every Generate so each press gives a new sheet
+    pub history_action: Option<HistoryAction>,
+    // State sets this while a crashed session's autosave waits for an answer
+    pub recovery_pending: bool,
+    pub recovery_action: Option<RecoveryAction>,
+    // Why the last restore failed, shown in the prompt
+    pub recovery_error: Option<String>,
+    // (undo, redo) steps available, filled in by State every frame
+    pub history_steps: (usize, usize),
+    // True while a canvas dimension is being dragged or typed, so the sim isn't rebuilt every frame
+    pub editing_canvas_size: bool,
+    // Latest post-projection divergence, filled in by State once the GPU reports it
+    pub divergence_stats: Option<DivergenceStats>,
 }
 
 impl Gui {
@@ -60,6 +287,25 @@ impl Gui {
             state,
             renderer,
             params: GuiParams::default(),
+            export_path: "painting.png".to_string(),
+            import_path: String::new(),
+            import_seed_velocity: false,
+            mask_pending: false,
+            mask_placement: MaskPlacement::default(),
+            mask_action: None,
+            file_request: None,
+            step_requested: false,
+            clear_walls_requested: false,
+            fit_requested: false,
+            grain_action: None,
+            grain_seed: 0,
+            history_action: None,
+            recovery_pending: false,
+            recovery_action: None,
+            recovery_error: None,
+            history_steps: (0, 0),
+            editing_canvas_size: false,
+            divergence_stats: None,
         }
     }
 
@@ -67,6 +313,16 @@ impl Gui {
         let _ = self.state.on_window_event(window, event);
     }
 
+    // True while the cursor is over the panel, so wheel and drags belong to it
+    pub fn wants_pointer(&self) -> bool {
+        self.context.is_pointer_over_area()
+    }
+
+    // True while a text field has focus, so typed digits don't turn the canvas
+    pub fn wants_keyboard(&self) -> bool {
+        self.context.wants_keyboard_input()
+    }
+
     pub fn render(
         &mut self,
         device: &Device,
@@ -85,26 +341,162 @@ impl Gui {
             .default_width(200.0)
             .show(&self.context, |ui| {
                 ui.heading("Settings");
+                ui.horizontal(|ui| {
+                    let (undo_steps, redo_steps) = self.history_steps;
+                    if ui
+                        .add_enabled(undo_steps > 0, egui::Button::new("Undo"))
+                        .on_hover_text("Ctrl+Z")
+                        .clicked()
+                    {
+                        self.history_action = Some(HistoryAction::Undo);
+                    }
+                    if ui
+                        .add_enabled(redo_steps > 0, egui::Button::new("Redo"))
+                        .on_hover_text("Ctrl+Shift+Z")
+                        .clicked()
+                    {
+                        self.history_action = Some(HistoryAction::Redo);
+                    }
+                    ui.label(format!("{undo_steps} / {redo_steps}"));
+                });
+                ui.add(
+                    egui::Slider::new(&mut self.params.undo_budget_mb, 64..=4096)
+                        .logarithmic(true)
+                        .text("Undo Memory (MB)"),
+                );
                 ui.separator();
 
                 ui.label("Brush Settings");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.params.tool, BrushTool::Paint, "Paint");
+                    ui.radio_value(&mut self.params.tool, BrushTool::Smudge, "Smudge");
+                });
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.params.tool, BrushTool::Wall, "Wall");
+                    ui.radio_value(&mut self.params.tool, BrushTool::EraseWall, "Erase Wall");
+                });
                 ui.add(egui::Slider::new(&mut self.params.brush_size, 1.0..=100.0).text("Size"));
+                ui.horizontal(|ui| {
+                    ui.label("Stabilizer:");
+                    ui.radio_value(&mut self.params.stabilizer, StabilizerMode::Off, "Off");
+                    ui.radio_value(
+                        &mut self.params.stabilizer,
+                        StabilizerMode::LazyMouse,
+                        "Lazy Mouse",
+                    );
+                    ui.radio_value(
+                        &mut self.params.stabilizer,
+                        StabilizerMode::Smooth,
+                        "Smooth",
+                    );
+                });
+                if self.params.stabilizer != StabilizerMode::Off {
+                    ui.add(
+                        egui::Slider::new(&mut self.params.stabilizer_strength, 0.0..=1.0)
+                            .text("Strength"),
+                    );
+                }
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.params.show_obstacles, "Show Walls");
+                    if ui.button("Clear Walls").clicked() {
+                        self.clear_walls_requested = true;
+                    }
+                });
 
                 ui.separator();
                 ui.label("View Settings");
-                ui.add(egui::Slider::new(&mut self.params.zoom_level, 0.1..=5.0).text("Zoom"));
+                ui.add(
+                    egui::Slider::new(&mut self.params.view.zoom, MIN_ZOOM..=MAX_ZOOM)
+                        .logarithmic(true)
+                        .text("Zoom"),
+                );
+                let mut degrees = self.params.view.rotation.to_degrees();
+                if ui
+                    .add(
+                        egui::Slider::new(&mut degrees, -180.0..=180.0)
+                            .suffix("°")
+                            .text("Rotation"),
+                    )
+                    .changed()
+                {
+                    self.params.view.rotation = degrees.to_radians();
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Fit to Window").clicked() {
+                        self.fit_requested = true;
+                    }
+                    if ui.button("Reset View").clicked() {
+                        self.params.view = ViewTransform::default();
+                    }
+                });
+                ui.label("Wheel: zoom. Middle or Space + drag: pan. 4 / 6: rotate, 5: upright. Home: fit.");
 
                 ui.separator();
                 ui.label("Canvas Dimensions");
-                ui.horizontal(|ui| {
-                    ui.label("W:");
-                    ui.add(egui::DragValue::new(&mut self.params.canvas_width));
-                    ui.label("H:");
-                    ui.add(egui::DragValue::new(&mut self.params.canvas_height));
-                });
+                let (w_response, h_response) = ui
+                    .horizontal(|ui| {
+                        ui.label("W:");
+                        let w = ui.add(
+                            egui::DragValue::new(&mut self.params.canvas_width)
+                                .range(MIN_CANVAS_SIZE..=MAX_CANVAS_SIZE),
+                        );
+                        ui.label("H:");
+                        let h = ui.add(
+                            egui::DragValue::new(&mut self.params.canvas_height)
+                                .range(MIN_CANVAS_SIZE..=MAX_CANVAS_SIZE),
+                        );
+                        (w, h)
+                    })
+                    .inner;
+                self.editing_canvas_size = [w_response, h_response]
+                    .iter()
+                    .any(|r| r.dragged() || r.has_focus());
                 ui.separator();
                 ui.label("Color");
                 ui.color_edit_button_rgba_unmultiplied(&mut self.params.brush_color);
+                ui.horizontal(|ui| {
+                    ui.label("Mixing:");
+                    ui.radio_value(&mut self.params.color_model, ColorModel::Rgb, "Light (RGB)");
+                    ui.radio_value(
+                        &mut self.params.color_model,
+                        ColorModel::Pigment,
+                        "Paint (Pigment)",
+                    );
+                    ui.radio_value(&mut self.params.color_model, ColorModel::Palette, "Palette");
+                });
+                // Palette mode paints the chosen pigment; the colour's alpha is still the amount
+                if self.params.color_model == ColorModel::Palette {
+                    egui::ComboBox::from_label("Pigment")
+                        .selected_text(PALETTE[self.params.pigment].name)
+                        .show_ui(ui, |ui| {
+                            for (i, pigment) in PALETTE.iter().enumerate() {
+                                ui.selectable_value(&mut self.params.pigment, i, pigment.name);
+                            }
+                        });
+                }
+                ui.separator();
+                ui.label("Time");
+                ui.add(
+                    egui::Slider::new(&mut self.params.time_scale, 0.1..=4.0)
+                        .logarithmic(true)
+                        .text("Time Scale"),
+                );
+                ui.horizontal(|ui| {
+                    let label = if self.params.paused {
+                        "Resume"
+                    } else {
+                        "Pause"
+                    };
+                    if ui.button(label).clicked() {
+                        self.params.paused = !self.params.paused;
+                    }
+                    if ui
+                        .add_enabled(self.params.paused, egui::Button::new("Step"))
+                        .clicked()
+                    {
+                        self.step_requested = true;
+                    }
+                });
                 ui.separator();
                 ui.label("Fluid Physics");
                 ui.add(
@@ -117,13 +509,265 @@ impl Gui {
                     egui::Slider::new(&mut self.params.velocity_factor, 0.0..=5.0)
                         .text("Velocity Factor"),
                 );
-                ui.add(egui::Slider::new(&mut self.params.viscosity, 0.0..=1.0).text("Viscosity"));
+                ui.add(egui::Slider::new(&mut self.params.viscosity, 0.0..=50.0).text("Viscosity"));
+                ui.add(egui::Slider::new(&mut self.params.ink_bleed, 0.0..=1.0).text("Ink Bleed"));
+                ui.add(egui::Slider::new(&mut self.params.vorticity, 0.0..=20.0).text("Vorticity"));
+                ui.horizontal(|ui| {
+                    ui.label("Flow:");
+                    ui.radio_value(
+                        &mut self.params.advection,
+                        AdvectionScheme::SemiLagrangian,
+                        "Soft",
+                    );
+                    ui.radio_value(
+                        &mut self.params.advection,
+                        AdvectionScheme::MacCormack,
+                        "Sharp (MacCormack)",
+                    );
+                });
+                ui.label("Paper");
+                ui.add(
+                    egui::Slider::new(&mut self.params.deposit_rate, 0.0..=5.0).text("Settling"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.params.drying_time, 0.5..=30.0)
+                        .logarithmic(true)
+                        .text("Drying Time (s)"),
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Paper Colour");
+                    ui.color_edit_button_rgb(&mut self.params.paper_color);
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Generate Grain").clicked() {
+                        self.grain_seed = self.grain_seed.wrapping_add(1);
+                        self.grain_action = Some(GrainAction::Generate);
+                    }
+                    if ui.button("Flat").clicked() {
+                        self.grain_action = Some(GrainAction::Flat);
+                    }
+                });
+                ui.add(
+                    egui::Slider::new(&mut self.params.grain_scale, 2.0..=128.0)
+                        .logarithmic(true)
+                        .text("Grain Size"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.params.grain_shading, 0.0..=1.0)
+                        .text("Grain Shading"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.params.granulation, 0.0..=1.0).text("Granulation"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.params.grain_absorption, 0.0..=1.0)
+                        .text("Absorption"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.params.grain_friction, 0.0..=20.0)
+                        .text("Grain Friction"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.params.edge_darkening, 0.0..=2.0)
+                        .text("Edge Darkening"),
+                );
+                ui.label("Edges");
+                egui::Grid::new("boundary_grid").show(ui, |ui| {
+                    for (edge, name) in ["Left", "Right", "Top", "Bottom"].into_iter().enumerate() {
+                        let before = self.params.boundary[edge];
+                        ui.label(name);
+                        egui::ComboBox::from_id_salt(name)
+                            .selected_text(before.label())
+                            .show_ui(ui, |ui| {
+                                for mode in BoundaryMode::ALL {
+                                    ui.selectable_value(
+                                        &mut self.params.boundary[edge],
+                                        mode,
+                                        mode.label(),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        // Periodic only makes sense per axis: keep the opposite edge in step
+                        let after = self.params.boundary[edge];
+                        let opposite = edge ^ 1;
+                        if after == BoundaryMode::Periodic {
+                            self.params.boundary[opposite] = BoundaryMode::Periodic;
+                        } else if before == BoundaryMode::Periodic && after != before {
+                            self.params.boundary[opposite] = BoundaryMode::Wall;
+                        }
+                    }
+                });
                 ui.separator();
-                ui.label("Tool Mode");
-                // Use a Checkbox (or Toggle Switch if you use a specific crate for it)
-                ui.checkbox(&mut self.params.smudge, "Smudge Tool");
+                ui.label("Pressure Solver");
+                ui.horizontal(|ui| {
+                    ui.radio_value(
+                        &mut self.params.pressure_solver,
+                        PressureSolver::Jacobi,
+                        "Jacobi",
+                    );
+                    ui.radio_value(
+                        &mut self.params.pressure_solver,
+                        PressureSolver::RedBlackSor,
+                        "Red-Black SOR",
+                    );
+                    ui.radio_value(
+                        &mut self.params.pressure_solver,
+                        PressureSolver::Multigrid,
+                        "Multigrid",
+                    );
+                });
+                if self.params.pressure_solver == PressureSolver::Multigrid {
+                    ui.add(
+                        egui::Slider::new(&mut self.params.multigrid_cycles, 1..=8)
+                            .text("V-Cycles"),
+                    );
+                } else {
+                    ui.add(
+                        egui::Slider::new(&mut self.params.pressure_iterations, 1..=200)
+                            .text("Iterations"),
+                    );
+                }
+                ui.checkbox(
+                    &mut self.params.adaptive_pressure,
+                    "Iterate to tolerance (slider = max)",
+                );
+                if self.params.adaptive_pressure {
+                    ui.add(
+                        egui::Slider::new(&mut self.params.divergence_tolerance, 0.0001..=1.0)
+                            .logarithmic(true)
+                            .text("Tolerance (RMS)"),
+                    );
+                }
+                match self.divergence_stats {
+                    Some(stats) => ui.label(format!(
+                        "Divergence error: RMS {:.2e}, max {:.2e} ({} it.)",
+                        stats.rms, stats.max, stats.iterations
+                    )),
+                    None => ui.label("Divergence error: -"),
+                };
+                ui.label("Diffusion Solver");
+                ui.horizontal(|ui| {
+                    ui.radio_value(
+                        &mut self.params.diffuse_solver,
+                        DiffuseSolver::Jacobi,
+                        "Jacobi",
+                    );
+                    ui.radio_value(
+                        &mut self.params.diffuse_solver,
+                        DiffuseSolver::RedBlackSor,
+                        "Red-Black SOR",
+                    );
+                });
+                ui.add(
+                    egui::Slider::new(&mut self.params.diffuse_iterations, 1..=200)
+                        .text("Iterations"),
+                );
+                if self.params.pressure_solver == PressureSolver::RedBlackSor
+                    || self.params.diffuse_solver == DiffuseSolver::RedBlackSor
+                {
+                    // 1.0 = plain Gauss-Seidel. Large grids want values close to 2.
+                    ui.add(
+                        egui::Slider::new(&mut self.params.sor_omega, 1.0..=1.99)
+                            .text("Over-Relaxation"),
+                    );
+                }
+                ui.separator();
+                ui.label("Save As");
+                ui.text_edit_singleline(&mut self.export_path);
+                ui.horizontal(|ui| {
+                    if ui.button("PNG").clicked() {
+                        self.file_request =
+                            Some(FileRequest::SavePng(self.export_path.clone().into()));
+                    }
+                    if ui.button("Raw Fields (PFM)").clicked() {
+                        self.file_request =
+                            Some(FileRequest::SaveRawFields(self.export_path.clone().into()));
+                    }
+                    if ui.button("Project (.fsp)").clicked() {
+                        let path = PathBuf::from(&self.export_path).with_extension("fsp");
+                        self.file_request = Some(FileRequest::SaveProject(path));
+                    }
+                });
+                ui.separator();
+                ui.label("Import Image");
+                ui.text_edit_singleline(&mut self.import_path);
+                ui.checkbox(
+                    &mut self.import_seed_velocity,
+                    "Seed velocity from luminance",
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Load as Ink").clicked() {
+                        self.file_request = Some(FileRequest::ImportImage {
+                            path: self.import_path.clone().into(),
+                            seed_velocity: self.import_seed_velocity,
+                        });
+                    }
+                    if ui.button("Load as Walls").clicked() {
+                        self.file_request =
+                            Some(FileRequest::ImportMask(self.import_path.clone().into()));
+                    }
+                    if ui.button("Load as Paper").clicked() {
+                        self.file_request =
+                            Some(FileRequest::ImportGrain(self.import_path.clone().into()));
+                    }
+                });
+                if ui.button("Open Project").clicked() {
+                    self.file_request =
+                        Some(FileRequest::OpenProject(self.import_path.clone().into()));
+                }
+                ui.add(
+                    egui::Slider::new(&mut self.params.autosave_interval, 0.0..=600.0)
+                        .suffix(" s")
+                        .text("Autosave (0 = off)"),
+                );
+                if self.mask_pending {
+                    // Position the mask over the canvas (orange preview), then commit it
+                    let placement = &mut self.mask_placement;
+                    ui.add(egui::Slider::new(&mut placement.offset[0], -0.5..=0.5).text("Mask X"));
+                    ui.add(egui::Slider::new(&mut placement.offset[1], -0.5..=0.5).text("Mask Y"));
+                    ui.add(
+                        egui::Slider::new(&mut placement.scale, 0.1..=4.0)
+                            .logarithmic(true)
+                            .text("Mask Scale"),
+                    );
+                    ui.checkbox(&mut placement.invert, "Invert (light = wall)");
+                    ui.horizontal(|ui| {
+                        if ui.button("Apply").clicked() {
+                            self.mask_action = Some(MaskAction::Apply);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.mask_action = Some(MaskAction::Cancel);
+                        }
+                    });
+                }
             });
 
+        if self.recovery_pending {
+            egui::Window::new("Restore previous session?")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(&self.context, |ui| {
+                    ui.label("The last session didn't shut down cleanly, but it left an autosave.");
+                    if let Some(error) = &self.recovery_error {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("Restore failed: {error}"),
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Restore").clicked() {
+                            self.recovery_action = Some(RecoveryAction::Restore);
+                        }
+                        if ui.button("Discard").clicked() {
+                            self.recovery_action = Some(RecoveryAction::Discard);
+                        }
+                    });
+                });
+        }
+
         // Tessellate shapes into primitives
         let output = self.context.end_pass();
         let primitives = self
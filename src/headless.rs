@@ -0,0 +1,142 @@
+use crate::canvas_mod::divergence_monitor::DivergenceStats;
+use crate::canvas_mod::fluid_sim::FluidSim;
+use crate::gui_mod::gui::GuiParams;
+use crate::timestep::SIM_DT;
+use crate::wgpu_utils::wgpu_init_headless;
+use anyhow::Context;
+use std::iter;
+
+// A single brush segment, in GRID coordinates, applied on a given step.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BrushEvent {
+    pub step: u32,
+    pub from: [f32; 2],
+    pub to: [f32; 2],
+}
+
+// Parses a brush script. One event per line: "<step> <from_x> <from_y> <to_x> <to_y>".
+// Blank lines and lines starting with '#' are ignored.
+// A step paints one segment at most, so a step given twice is an error.
+pub fn parse_script(text: &str) -> anyhow::Result<Vec<BrushEvent>> {
+    let mut events: Vec<(usize, BrushEvent)> = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 5 {
+            anyhow::bail!(
+                "line {}: expected 5 fields (step from_x from_y to_x to_y), got {}",
+                line_no + 1,
+                fields.len()
+            );
+        }
+
+        let number = |i: usize| -> anyhow::Result<f32> {
+            fields[i]
+                .parse::<f32>()
+                .with_context(|| format!("line {}: bad number '{}'", line_no + 1, fields[i]))
+        };
+        let event = BrushEvent {
+            step: fields[0]
+                .parse()
+                .with_context(|| format!("line {}: bad step '{}'", line_no + 1, fields[0]))?,
+            from: [number(1)?, number(2)?],
+            to: [number(3)?, number(4)?],
+        };
+        if let Some((first, _)) = events.iter().find(|(_, other)| other.step == event.step) {
+            anyhow::bail!(
+                "line {}: step {} already has a segment (line {first})",
+                line_no + 1,
+                event.step
+            );
+        }
+        events.push((line_no + 1, event));
+    }
+    Ok(events.into_iter().map(|(_, event)| event).collect())
+}
+
+// The simulation without a window or surface.
+// Owns its own device so it can run on machines with no display (and no GPU, via the fallback adapter).
+pub struct HeadlessSim {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub sim: FluidSim,
+    pub steps_taken: u32,
+}
+
+impl HeadlessSim {
+    pub async fn new(width: u32, height: u32, force_fallback_adapter: bool) -> anyhow::Result<Self> {
+        let (device, queue) = wgpu_init_headless(force_fallback_adapter).await?;
+        let sim = FluidSim::new(&device, width, height);
+
+        Ok(Self {
+            device,
+            queue,
+            sim,
+            steps_taken: 0,
+        })
+    }
+
+    // Advances the simulation by one fixed step (scaled by params.time_scale), optionally painting a segment.
+    pub fn step(&mut self, params: &GuiParams, brush: Option<&BrushEvent>) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Headless Step Encoder"),
+            });
+
+        let stroke = brush.map(|event| [event.from, event.to]);
+        self.sim.step(
+            &self.queue,
+            &mut encoder,
+            params,
+            stroke.as_ref().map(|stroke| stroke.as_slice()),
+            false,
+            SIM_DT * params.time_scale,
+        );
+
+        self.queue.submit(iter::once(encoder.finish()));
+        self.sim.after_submit(&self.device);
+        self.steps_taken += 1;
+    }
+
+    // Runs 'steps' steps, applying the script event whose step matches (parse_script allows one per step).
+    // Steps are counted from the first call, so a script can be fed in several chunks.
+    pub fn run(&mut self, params: &GuiParams, steps: u32, script: &[BrushEvent]) {
+        for _ in 0..steps {
+            let event = script.iter().find(|event| event.step == self.steps_taken);
+            self.step(params, event);
+        }
+    }
+
+    // Runs the pressure solver selected in 'params' alone on 'divergence' (one value per cell),
+    // warm-started from the current pressure, and reads the result back.
+    pub fn solve_pressure(&mut self, params: &GuiParams, divergence: &[f32]) -> Vec<f32> {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Headless Pressure Encoder"),
+            });
+        self.sim.upload_divergence(&self.queue, divergence);
+        self.sim.solve_pressure(&self.queue, &mut encoder, params);
+        self.queue.submit(iter::once(encoder.finish()));
+        self.sim.read_pressure(&self.device, &self.queue)
+    }
+
+    pub fn read_density(&self) -> Vec<[f32; 4]> {
+        self.sim.read_density(&self.device, &self.queue)
+    }
+
+    pub fn read_velocity(&self) -> Vec<[f32; 2]> {
+        self.sim.read_velocity(&self.device, &self.queue)
+    }
+
+    // Latest post-projection divergence. Picks up a reading the GPU has finished but nobody polled.
+    pub fn divergence_stats(&mut self) -> Option<DivergenceStats> {
+        self.sim.after_submit(&self.device);
+        self.sim.divergence_stats()
+    }
+}
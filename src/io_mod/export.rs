@@ -0,0 +1,136 @@
+use crate::canvas_mod::pigment::ks_to_reflectance;
+use crate::gui_mod::gui::ColorModel;
+use anyhow::Context;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// CPU copy of the fs_main composite: ink blended onto paper by density, or in pigment mode
+// the Kubelka–Munk reflectance of the stored K/S values. 'paper' is the (grain-shaded) paper
+// colour under the cell, see grain::paper_shade.
+// Returns a LINEAR colour, exactly what the fragment shader writes before the sRGB surface encodes it.
+pub fn composite_pixel(cell: [f32; 4], paper: [f32; 3], color_model: ColorModel) -> [f32; 3] {
+    if color_model == ColorModel::Pigment {
+        return [
+            paper[0] * ks_to_reflectance(cell[0]),
+            paper[1] * ks_to_reflectance(cell[1]),
+            paper[2] * ks_to_reflectance(cell[2]),
+        ];
+    }
+    let density = cell[3];
+    [
+        paper[0] + (cell[0] - paper[0]) * density,
+        paper[1] + (cell[1] - paper[1]) * density,
+        paper[2] + (cell[2] - paper[2]) * density,
+    ]
+}
+
+// Puts the settled ink (paper layer) under the liquid ink so composite_pixel sees one cell.
+// RGB layers stack like fs_main blends them; K/S values and pigment concentrations add up.
+pub fn flatten_layers(fluid: [f32; 4], paper: [f32; 4], color_model: ColorModel) -> [f32; 4] {
+    if color_model != ColorModel::Rgb {
+        return [0, 1, 2, 3].map(|i| fluid[i] + paper[i]);
+    }
+    let amount = fluid[3] + paper[3] * (1.0 - fluid[3]);
+    if amount <= 0.0 {
+        return [0.0; 4];
+    }
+    let rgb = |i: usize| (fluid[i] * fluid[3] + paper[i] * paper[3] * (1.0 - fluid[3])) / amount;
+    [rgb(0), rgb(1), rgb(2), amount]
+}
+
+// Linear -> sRGB transfer function (IEC 61966-2-1), quantised to 8 bits.
+pub fn linear_to_srgb8(value: f32) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let encoded = if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
+
+// Writes the composited painting as an 8-bit sRGB PNG.
+// 'density' is the readback of density_a (row-major, top row first), with the paper layer
+// merged in by flatten_layers. 'paper' is the paper colour per cell. Palette mode has no
+// composite here: convert with pigment::palette_cell_to_pigment and pass ColorModel::Pigment.
+pub fn save_png(
+    path: &Path,
+    width: u32,
+    height: u32,
+    density: &[[f32; 4]],
+    paper: &[[f32; 3]],
+    color_model: ColorModel,
+) -> anyhow::Result<()> {
+    let pixels: Vec<u8> = density
+        .iter()
+        .zip(paper)
+        .flat_map(|(&cell, &paper)| {
+            let [r, g, b] = composite_pixel(cell, paper, color_model);
+            [
+                linear_to_srgb8(r),
+                linear_to_srgb8(g),
+                linear_to_srgb8(b),
+                255,
+            ]
+        })
+        .collect();
+
+    image::save_buffer(path, &pixels, width, height, image::ColorType::Rgba8)
+        .with_context(|| format!("failed to write PNG to {}", path.display()))
+}
+
+// Writes the raw simulation fields as full-float PFM images next to 'path':
+//   <stem>_ink.pfm      RGB ink colour (K/S per channel in pigment mode,
+//                       concentrations of pigments 0-2 in palette mode)
+//   <stem>_density.pfm  ink amount (greyscale)
+//   <stem>_velocity.pfm velocity in cells/second (R = x, G = y, B = 0)
+pub fn save_raw_fields(
+    path: &Path,
+    width: u32,
+    height: u32,
+    density: &[[f32; 4]],
+    velocity: &[[f32; 2]],
+) -> anyhow::Result<()> {
+    let sibling = |suffix: &str| {
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "canvas".to_string());
+        path.with_file_name(format!("{stem}_{suffix}.pfm"))
+    };
+
+    let ink: Vec<f32> = density.iter().flat_map(|c| [c[0], c[1], c[2]]).collect();
+    let amount: Vec<f32> = density.iter().map(|c| c[3]).collect();
+    let vel: Vec<f32> = velocity.iter().flat_map(|v| [v[0], v[1], 0.0]).collect();
+
+    write_pfm(&sibling("ink"), width, height, 3, &ink)?;
+    write_pfm(&sibling("density"), width, height, 1, &amount)?;
+    write_pfm(&sibling("velocity"), width, height, 3, &vel)?;
+    Ok(())
+}
+
+// Portable Float Map. Little-endian (negative scale), rows stored BOTTOM to top.
+fn write_pfm(
+    path: &Path,
+    width: u32,
+    height: u32,
+    channels: usize,
+    data: &[f32],
+) -> anyhow::Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+
+    let magic = if channels == 3 { "PF" } else { "Pf" };
+    write!(out, "{magic}\n{width} {height}\n-1.0\n")?;
+
+    let row_len = width as usize * channels;
+    for row in data.chunks(row_len).rev() {
+        for value in row {
+            out.write_all(&value.to_le_bytes())?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
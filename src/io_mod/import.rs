@@ -0,0 +1,154 @@
+use anyhow::Context;
+use std::path::Path;
+
+// sRGB -> linear transfer function (IEC 61966-2-1). Inverse of export::linear_to_srgb8.
+pub fn srgb8_to_linear(value: u8) -> f32 {
+    let v = value as f32 / 255.0;
+    if v <= 0.040_45 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Decodes an image file and resamples it to the sim grid.
+// Returns density cells: RGB = linear ink colour, A = ink amount (the image alpha).
+pub fn load_ink_image(path: &Path, width: u32, height: u32) -> anyhow::Result<Vec<[f32; 4]>> {
+    let decoded = image::open(path)
+        .with_context(|| format!("failed to decode {}", path.display()))?
+        .to_rgba8();
+    let resized = image::imageops::resize(
+        &decoded,
+        width,
+        height,
+        image::imageops::FilterType::Triangle,
+    );
+
+    Ok(resized
+        .pixels()
+        .map(|p| {
+            [
+                srgb8_to_linear(p[0]),
+                srgb8_to_linear(p[1]),
+                srgb8_to_linear(p[2]),
+                p[3] as f32 / 255.0,
+            ]
+        })
+        .collect())
+}
+
+// Seeds a velocity field that swirls along the image's luminance contours.
+// The velocity is the luminance gradient rotated by 90 degrees, so edges start flowing
+// ALONG themselves instead of smearing across. 'strength' is in cells/second per unit gradient.
+pub fn luminance_velocity_seed(
+    ink: &[[f32; 4]],
+    width: u32,
+    height: u32,
+    strength: f32,
+) -> Vec<[f32; 2]> {
+    let (w, h) = (width as usize, height as usize);
+    let luminance: Vec<f32> = ink
+        .iter()
+        .map(|c| (0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2]) * c[3])
+        .collect();
+    let lum = |x: usize, y: usize| luminance[y * w + x];
+
+    let mut velocity = vec![[0.0; 2]; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            // Central differences, clamped at the edges
+            let gx = lum((x + 1).min(w - 1), y) - lum(x.saturating_sub(1), y);
+            let gy = lum(x, (y + 1).min(h - 1)) - lum(x, y.saturating_sub(1));
+            velocity[y * w + x] = [-gy * 0.5 * strength, gx * 0.5 * strength];
+        }
+    }
+    velocity
+}
+
+// Where an imported obstacle mask lands on the canvas. The image is first fitted inside the
+// canvas (keeping its aspect ratio), then scaled about its centre and moved.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MaskPlacement {
+    pub offset: [f32; 2], // Of the image centre from the canvas centre, as a fraction of the canvas
+    pub scale: f32,
+    pub invert: bool, // Light pixels become solid instead of dark ones
+}
+
+impl Default for MaskPlacement {
+    fn default() -> Self {
+        Self {
+            offset: [0.0, 0.0],
+            scale: 1.0,
+            invert: false,
+        }
+    }
+}
+
+// Decodes a (black and white) image for use as an obstacle mask. Placement happens later,
+// so the image is kept at its own resolution.
+pub fn load_mask_image(path: &Path) -> anyhow::Result<image::GrayAlphaImage> {
+    Ok(image::open(path)
+        .with_context(|| format!("failed to decode {}", path.display()))?
+        .to_luma_alpha8())
+}
+
+// Rescales the mask onto the sim grid. Returns one obstacle cell per grid cell (1.0 = solid).
+// Dark, opaque pixels are solid (light ones when inverted); transparent pixels and everything
+// outside the image never are.
+pub fn place_mask(
+    mask: &image::GrayAlphaImage,
+    placement: &MaskPlacement,
+    width: u32,
+    height: u32,
+) -> Vec<f32> {
+    let (image_w, image_h) = (mask.width() as f32, mask.height() as f32);
+    let cells_per_pixel =
+        (width as f32 / image_w).min(height as f32 / image_h) * placement.scale.max(1e-3);
+    let centre = [
+        width as f32 * (0.5 + placement.offset[0]),
+        height as f32 * (0.5 + placement.offset[1]),
+    ];
+
+    let mut cells = vec![0.0; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            // Cell centre -> image pixel (nearest)
+            let px = ((x as f32 + 0.5 - centre[0]) / cells_per_pixel + image_w * 0.5).floor();
+            let py = ((y as f32 + 0.5 - centre[1]) / cells_per_pixel + image_h * 0.5).floor();
+            if px < 0.0 || py < 0.0 || px >= image_w || py >= image_h {
+                continue;
+            }
+
+            let pixel = mask.get_pixel(px as u32, py as u32);
+            let luma = pixel[0] as f32 / 255.0;
+            let alpha = pixel[1] as f32 / 255.0;
+            let darkness = if placement.invert { luma } else { 1.0 - luma };
+            if darkness * alpha >= 0.5 {
+                cells[(y * width + x) as usize] = 1.0;
+            }
+        }
+    }
+    cells
+}
+
+// Decodes a paper texture (a height map: light = high) and resamples it to the sim grid.
+// Returns one relief value per cell, -1 (valley) to +1 (ridge), centred on the image's mean
+// so the paper as a whole neither absorbs nor settles more than flat paper.
+pub fn load_grain_image(path: &Path, width: u32, height: u32) -> anyhow::Result<Vec<f32>> {
+    let decoded = image::open(path)
+        .with_context(|| format!("failed to decode {}", path.display()))?
+        .to_luma8();
+    let resized = image::imageops::resize(
+        &decoded,
+        width,
+        height,
+        image::imageops::FilterType::Triangle,
+    );
+
+    let heights: Vec<f32> = resized.pixels().map(|p| p[0] as f32 / 255.0).collect();
+    let mean = heights.iter().sum::<f32>() / heights.len().max(1) as f32;
+    Ok(heights
+        .iter()
+        .map(|h| ((h - mean) * 2.0).clamp(-1.0, 1.0))
+        .collect())
+}
@@ -0,0 +1,501 @@
+// --- PROJECT FILES (.fsp) ---
+// The whole live painting: every field texture the sim steps from, the settings and the view,
+// so a painting can be closed and reopened still flowing.
+//
+// Layout (all numbers little-endian):
+//   "FSP\0"  magic
+//   u16 major, u16 minor  format version
+//   chunks until the end of the file, each: [u8; 4] tag, u64 payload length, payload
+//     "HEAD"  u32 width, u32 height (cells)
+//     "PARM"  UTF-8 text, one "key = value" line per GuiParams field, except the app preferences
+//             (undo_budget_mb, autosave_interval), which belong to the install, not the painting
+//     "FELD"  u32 name length, name, u32 channels, width * height * channels f32 (row-major,
+//             top row first, like the readbacks)
+//
+// Forward compatibility: readers skip chunks, settings and fields they don't know, and anything
+// a file doesn't have keeps its default. Additions like that bump the MINOR version only.
+// A newer MAJOR version means the layout above changed, and is refused.
+//
+// Nothing read is trusted: a canvas size outside what the panel allows is refused, and settings
+// are clamped to the panel's ranges (a bad or non-finite value keeps its default).
+use crate::canvas_mod::pigment::PALETTE;
+use crate::canvas_mod::view::{MAX_ZOOM, MIN_ZOOM, ViewTransform};
+use crate::gui_mod::gui::{
+    AdvectionScheme, BoundaryMode, BrushTool, ColorModel, DiffuseSolver, GuiParams,
+    MAX_CANVAS_SIZE, MIN_CANVAS_SIZE, PressureSolver, StabilizerMode,
+};
+use anyhow::Context;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+pub const MAGIC: [u8; 4] = *b"FSP\0";
+pub const VERSION_MAJOR: u16 = 1;
+pub const VERSION_MINOR: u16 = 0;
+
+const TAG_HEADER: [u8; 4] = *b"HEAD";
+const TAG_PARAMS: [u8; 4] = *b"PARM";
+const TAG_FIELD: [u8; 4] = *b"FELD";
+
+// One sim texture, as read back from the GPU
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectField {
+    pub name: String, // See FluidSim::project_fields
+    pub channels: u32,
+    pub data: Vec<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Project {
+    pub width: u32,
+    pub height: u32,
+    pub params: GuiParams,
+    pub fields: Vec<ProjectField>,
+}
+
+impl Project {
+    pub fn field(&self, name: &str) -> Option<&ProjectField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+pub fn save_project(path: &Path, project: &Project) -> anyhow::Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    write_project(&mut out, project)?;
+    out.flush()?;
+    Ok(())
+}
+
+pub fn load_project(path: &Path) -> anyhow::Result<Project> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    read_project(&mut BufReader::new(file))
+        .with_context(|| format!("failed to read project {}", path.display()))
+}
+
+pub fn write_project(out: &mut impl Write, project: &Project) -> anyhow::Result<()> {
+    out.write_all(&MAGIC)?;
+    out.write_all(&VERSION_MAJOR.to_le_bytes())?;
+    out.write_all(&VERSION_MINOR.to_le_bytes())?;
+
+    let mut header = Vec::with_capacity(8);
+    header.extend(project.width.to_le_bytes());
+    header.extend(project.height.to_le_bytes());
+    write_chunk(out, TAG_HEADER, &header)?;
+
+    write_chunk(out, TAG_PARAMS, encode_params(&project.params).as_bytes())?;
+
+    let cells = project.width as usize * project.height as usize;
+    for field in &project.fields {
+        anyhow::ensure!(
+            field.data.len() == cells * field.channels as usize,
+            "field '{}' has {} values, expected {} x {} channels",
+            field.name,
+            field.data.len(),
+            cells,
+            field.channels
+        );
+        let mut payload = Vec::with_capacity(8 + field.name.len() + field.data.len() * 4);
+        payload.extend((field.name.len() as u32).to_le_bytes());
+        payload.extend(field.name.as_bytes());
+        payload.extend(field.channels.to_le_bytes());
+        payload.extend(bytemuck::cast_slice::<f32, u8>(&field.data));
+        write_chunk(out, TAG_FIELD, &payload)?;
+    }
+    Ok(())
+}
+
+pub fn read_project(input: &mut impl Read) -> anyhow::Result<Project> {
+    let mut magic = [0; 4];
+    input.read_exact(&mut magic).context("file is empty")?;
+    anyhow::ensure!(magic == MAGIC, "not a project file");
+    let major = read_u16(input)?;
+    let _minor = read_u16(input)?; // Minor versions only add things, which are skipped below
+    anyhow::ensure!(
+        major <= VERSION_MAJOR,
+        "project format {major} is newer than this build understands ({VERSION_MAJOR})"
+    );
+
+    let mut size = None;
+    let mut params = GuiParams::default();
+    let mut fields = Vec::new();
+    loop {
+        let mut tag = [0; 4];
+        match input.read_exact(&mut tag) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let length = read_u64(input)?;
+        let mut payload = Vec::new();
+        input.take(length).read_to_end(&mut payload)?;
+        anyhow::ensure!(
+            payload.len() as u64 == length,
+            "chunk {} is cut short",
+            String::from_utf8_lossy(&tag)
+        );
+
+        match tag {
+            TAG_HEADER => {
+                anyhow::ensure!(payload.len() >= 8, "header chunk too short");
+                let width = u32::from_le_bytes(payload[0..4].try_into()?);
+                let height = u32::from_le_bytes(payload[4..8].try_into()?);
+                let sizes = MIN_CANVAS_SIZE..=MAX_CANVAS_SIZE;
+                anyhow::ensure!(
+                    sizes.contains(&width) && sizes.contains(&height),
+                    "canvas size {width} x {height} is outside {MIN_CANVAS_SIZE}..={MAX_CANVAS_SIZE}"
+                );
+                size = Some((width, height));
+            }
+            TAG_PARAMS => {
+                let text = std::str::from_utf8(&payload).context("settings aren't UTF-8")?;
+                decode_params(&mut params, text);
+            }
+            TAG_FIELD => fields.push(decode_field(&payload)?),
+            _ => log::info!(
+                "Skipping unknown project chunk '{}'",
+                String::from_utf8_lossy(&tag)
+            ),
+        }
+    }
+
+    let (width, height) = size.context("project has no header")?;
+    let cells = width as usize * height as usize;
+    for field in &fields {
+        anyhow::ensure!(
+            field.data.len() == cells * field.channels as usize,
+            "field '{}' doesn't match the {width} x {height} canvas",
+            field.name
+        );
+    }
+    params.canvas_width = width;
+    params.canvas_height = height;
+    Ok(Project {
+        width,
+        height,
+        params,
+        fields,
+    })
+}
+
+fn write_chunk(out: &mut impl Write, tag: [u8; 4], payload: &[u8]) -> anyhow::Result<()> {
+    out.write_all(&tag)?;
+    out.write_all(&(payload.len() as u64).to_le_bytes())?;
+    out.write_all(payload)?;
+    Ok(())
+}
+
+fn read_u16(input: &mut impl Read) -> anyhow::Result<u16> {
+    let mut bytes = [0; 2];
+    input.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u64(input: &mut impl Read) -> anyhow::Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn decode_field(payload: &[u8]) -> anyhow::Result<ProjectField> {
+    let u32_at = |offset: usize| -> anyhow::Result<u32> {
+        let bytes = payload
+            .get(offset..offset + 4)
+            .context("field chunk too short")?;
+        Ok(u32::from_le_bytes(bytes.try_into()?))
+    };
+    let name_len = u32_at(0)? as usize;
+    let name = payload
+        .get(4..4 + name_len)
+        .context("field chunk too short")?;
+    let name = String::from_utf8(name.to_vec()).context("field name isn't UTF-8")?;
+    let channels = u32_at(4 + name_len)?;
+    let values = &payload[8 + name_len..];
+    anyhow::ensure!(
+        values.len().is_multiple_of(4),
+        "field '{name}' is cut short"
+    );
+    // The payload isn't 4-byte aligned in general, so no cast_slice here
+    let data = values
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect();
+    Ok(ProjectField {
+        name,
+        channels,
+        data,
+    })
+}
+
+// --- SETTINGS ---
+// GuiParams as "key = value" lines. Floats use Rust's shortest round-trip formatting, so a
+// value reads back bit for bit.
+trait Setting {
+    fn encode(&self) -> String;
+    // False if 'text' isn't a valid value (the setting keeps its current one)
+    fn decode(&mut self, text: &str) -> bool;
+}
+
+macro_rules! parsed_setting {
+    ($($ty:ty),*) => {$(
+        impl Setting for $ty {
+            fn encode(&self) -> String {
+                self.to_string()
+            }
+            fn decode(&mut self, text: &str) -> bool {
+                text.parse().map(|value| *self = value).is_ok()
+            }
+        }
+    )*};
+}
+parsed_setting!(u32, usize, bool);
+
+// "inf" and "NaN" parse, but no setting can take them
+fn parse_finite(text: &str) -> Option<f32> {
+    text.parse::<f32>().ok().filter(|value| value.is_finite())
+}
+
+impl Setting for f32 {
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+    fn decode(&mut self, text: &str) -> bool {
+        parse_finite(text).map(|value| *self = value).is_some()
+    }
+}
+
+impl<const N: usize> Setting for [f32; N] {
+    fn encode(&self) -> String {
+        self.map(|value| value.to_string()).join(" ")
+    }
+    fn decode(&mut self, text: &str) -> bool {
+        let values: Option<Vec<f32>> = text.split_whitespace().map(parse_finite).collect();
+        match values.and_then(|values| <[f32; N]>::try_from(values).ok()) {
+            Some(values) => {
+                *self = values;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// Enums are stored by variant name, so reordering variants doesn't break old files
+macro_rules! enum_setting {
+    ($ty:ident { $($variant:ident),* $(,)? }) => {
+        impl Setting for $ty {
+            fn encode(&self) -> String {
+                match self {
+                    $($ty::$variant => stringify!($variant).to_string(),)*
+                }
+            }
+            fn decode(&mut self, text: &str) -> bool {
+                match text {
+                    $(stringify!($variant) => *self = $ty::$variant,)*
+                    _ => return false,
+                }
+                true
+            }
+        }
+    };
+}
+enum_setting!(ColorModel {
+    Rgb,
+    Pigment,
+    Palette
+});
+enum_setting!(BrushTool {
+    Paint,
+    Smudge,
+    Wall,
+    EraseWall
+});
+enum_setting!(StabilizerMode {
+    Off,
+    LazyMouse,
+    Smooth
+});
+enum_setting!(AdvectionScheme {
+    SemiLagrangian,
+    MacCormack
+});
+enum_setting!(PressureSolver {
+    Jacobi,
+    RedBlackSor,
+    Multigrid
+});
+enum_setting!(DiffuseSolver {
+    Jacobi,
+    RedBlackSor
+});
+enum_setting!(BoundaryMode {
+    Wall,
+    FreeSlip,
+    Periodic,
+    Open
+});
+
+// A setting limited to what the panel allows: out-of-range values are clamped on decoding
+// (a damaged file, or one from a build with wider limits, can't index past the palette or
+// ask for a billion solver iterations)
+struct Ranged<'a, T>(&'a mut T, RangeInclusive<T>);
+
+impl<T: Setting + PartialOrd + Copy> Setting for Ranged<'_, T> {
+    fn encode(&self) -> String {
+        self.0.encode()
+    }
+    fn decode(&mut self, text: &str) -> bool {
+        if !self.0.decode(text) {
+            return false;
+        }
+        let (min, max) = (*self.1.start(), *self.1.end());
+        if *self.0 < min {
+            *self.0 = min;
+        } else if *self.0 > max {
+            *self.0 = max;
+        }
+        true
+    }
+}
+
+// Every saved setting with its key, and the range of its slider. New GuiParams fields go here;
+// keys must never change.
+// (The canvas size isn't listed: it comes from the header, which the fields must match.)
+fn visit_params(params: &mut GuiParams, visit: &mut dyn FnMut(&str, &mut dyn Setting)) {
+    let ViewTransform {
+        pan,
+        zoom,
+        rotation,
+    } = &mut params.view;
+    visit("view.pan", pan);
+    visit("view.zoom", &mut Ranged(zoom, MIN_ZOOM..=MAX_ZOOM));
+    visit("view.rotation", rotation);
+    visit(
+        "brush_size",
+        &mut Ranged(&mut params.brush_size, 1.0..=100.0),
+    );
+    visit("brush_color", &mut params.brush_color);
+    visit("color_model", &mut params.color_model);
+    visit(
+        "pigment",
+        &mut Ranged(&mut params.pigment, 0..=PALETTE.len() - 1),
+    );
+    visit(
+        "velocity_decay",
+        &mut Ranged(&mut params.velocity_decay, 0.0..=1.0),
+    );
+    visit("ink_decay", &mut Ranged(&mut params.ink_decay, 0.9..=1.0));
+    visit(
+        "velocity_factor",
+        &mut Ranged(&mut params.velocity_factor, 0.0..=5.0),
+    );
+    visit("viscosity", &mut Ranged(&mut params.viscosity, 0.0..=50.0));
+    visit("ink_bleed", &mut Ranged(&mut params.ink_bleed, 0.0..=1.0));
+    visit(
+        "deposit_rate",
+        &mut Ranged(&mut params.deposit_rate, 0.0..=5.0),
+    );
+    visit(
+        "drying_time",
+        &mut Ranged(&mut params.drying_time, 0.5..=30.0),
+    );
+    visit("paper_color", &mut params.paper_color);
+    visit(
+        "grain_scale",
+        &mut Ranged(&mut params.grain_scale, 2.0..=128.0),
+    );
+    visit(
+        "grain_shading",
+        &mut Ranged(&mut params.grain_shading, 0.0..=1.0),
+    );
+    visit(
+        "granulation",
+        &mut Ranged(&mut params.granulation, 0.0..=1.0),
+    );
+    visit(
+        "grain_absorption",
+        &mut Ranged(&mut params.grain_absorption, 0.0..=1.0),
+    );
+    visit(
+        "grain_friction",
+        &mut Ranged(&mut params.grain_friction, 0.0..=20.0),
+    );
+    visit(
+        "edge_darkening",
+        &mut Ranged(&mut params.edge_darkening, 0.0..=2.0),
+    );
+    visit("tool", &mut params.tool);
+    visit("stabilizer", &mut params.stabilizer);
+    visit(
+        "stabilizer_strength",
+        &mut Ranged(&mut params.stabilizer_strength, 0.0..=1.0),
+    );
+    visit("show_obstacles", &mut params.show_obstacles);
+    visit("advection", &mut params.advection);
+    visit("vorticity", &mut Ranged(&mut params.vorticity, 0.0..=20.0));
+    let [left, right, top, bottom] = &mut params.boundary;
+    visit("boundary.left", left);
+    visit("boundary.right", right);
+    visit("boundary.top", top);
+    visit("boundary.bottom", bottom);
+    visit("time_scale", &mut Ranged(&mut params.time_scale, 0.1..=4.0));
+    visit("paused", &mut params.paused);
+    visit("pressure_solver", &mut params.pressure_solver);
+    visit(
+        "pressure_iterations",
+        &mut Ranged(&mut params.pressure_iterations, 1..=200),
+    );
+    visit(
+        "multigrid_cycles",
+        &mut Ranged(&mut params.multigrid_cycles, 1..=8),
+    );
+    visit("adaptive_pressure", &mut params.adaptive_pressure);
+    visit(
+        "divergence_tolerance",
+        &mut Ranged(&mut params.divergence_tolerance, 0.0001..=1.0),
+    );
+    visit("diffuse_solver", &mut params.diffuse_solver);
+    visit(
+        "diffuse_iterations",
+        &mut Ranged(&mut params.diffuse_iterations, 1..=200),
+    );
+    visit("sor_omega", &mut Ranged(&mut params.sor_omega, 1.0..=1.99));
+}
+
+fn encode_params(params: &GuiParams) -> String {
+    let mut copy = params.clone(); // The visitor hands out &mut; nothing is changed
+    let mut text = String::new();
+    visit_params(&mut copy, &mut |key, value| {
+        text.push_str(&format!("{key} = {}\n", value.encode()));
+    });
+    text
+}
+
+// Applies every line it understands. Unknown keys come from newer versions and are skipped;
+// a bad value keeps the default.
+fn decode_params(params: &mut GuiParams, text: &str) {
+    let mut values: Vec<(&str, &str)> = text
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect();
+    visit_params(params, &mut |key, setting| {
+        if let Some(index) = values.iter().position(|(k, _)| *k == key) {
+            let (_, value) = values.swap_remove(index);
+            if !setting.decode(value) {
+                log::warn!("Ignoring bad project setting {key} = {value}");
+            }
+        }
+    });
+    for (key, _) in values {
+        log::info!("Skipping unknown project setting '{key}'");
+    }
+    // The panel only sets Periodic per axis (left/right, top/bottom); neither can the file
+    for axis in params.boundary.chunks_exact_mut(2) {
+        if axis.contains(&BoundaryMode::Periodic) {
+            axis.fill(BoundaryMode::Periodic);
+        }
+    }
+}
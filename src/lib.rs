@@ -1,6 +1,11 @@
 pub mod canvas_mod;
 pub mod gui_mod;
+pub mod headless;
+pub mod io_mod;
+pub mod recovery;
+pub mod stabilizer;
 pub mod state;
+pub mod timestep;
 pub mod wgpu_utils;
 
 use state::State;
@@ -20,6 +25,8 @@ pub struct App {
     #[cfg(target_arch = "wasm32")]
     proxy: Option<winit::event_loop::EventLoopProxy<State>>,
     state: Option<State>,
+    // Why the app couldn't start, handed back to run() once the event loop has stopped
+    startup_error: Option<anyhow::Error>,
 }
 
 impl App {
@@ -28,6 +35,7 @@ impl App {
         let proxy = Some(event_loop.create_proxy());
         Self {
             state: None,
+            startup_error: None,
             #[cfg(target_arch = "wasm32")]
             proxy,
         }
@@ -59,11 +67,24 @@ impl ApplicationHandler<State> for App {
             window_attributes = window_attributes.with_canvas(Some(html_canvas_element));
         }
 
-        let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
+        let window = match event_loop.create_window(window_attributes) {
+            Ok(window) => Arc::new(window),
+            Err(e) => {
+                self.startup_error = Some(anyhow::Error::new(e).context("unable to open a window"));
+                event_loop.exit();
+                return;
+            }
+        };
 
         #[cfg(not(target_arch = "wasm32"))]
         {
-            self.state = Some(pollster::block_on(State::new(window)).unwrap());
+            match pollster::block_on(State::new(window)) {
+                Ok(state) => self.state = Some(state),
+                Err(e) => {
+                    self.startup_error = Some(e);
+                    event_loop.exit();
+                }
+            }
         }
 
         #[cfg(target_arch = "wasm32")]
@@ -97,6 +118,13 @@ impl ApplicationHandler<State> for App {
         self.state = Some(event);
     }
 
+    // Every way out of the event loop (closing the window, Escape) passes here
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(state) = &mut self.state {
+            state.shutdown();
+        }
+    }
+
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -140,6 +168,8 @@ impl ApplicationHandler<State> for App {
             } => {
                 state.handle_click(button_state, button);
             }
+            WindowEvent::MouseWheel { delta, .. } => state.handle_wheel(delta),
+            WindowEvent::ModifiersChanged(modifiers) => state.handle_modifiers(modifiers.state()),
             _ => {}
         }
     }
@@ -157,7 +187,10 @@ pub fn run() -> anyhow::Result<()> {
         &event_loop,
     );
     event_loop.run_app(&mut app)?;
-    Ok(())
+    match app.startup_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -1,4 +1,7 @@
 use fluid_sim_paint::run;
 fn main() {
-    run().unwrap();
+    if let Err(e) = run() {
+        eprintln!("fluid_sim_paint: {e:#}");
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,402 @@
+use crate::canvas_mod::resources::texture::{StagedTexels, Texture};
+use crate::gui_mod::gui::GuiParams;
+use crate::io_mod::project::{Project, ProjectField, save_project};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
+use wgpu::{BufferAsyncError, CommandEncoder, Device};
+
+const LOCK_PREFIX: &str = "session-";
+const LOCK_SUFFIX: &str = ".lock";
+
+// Where the recovery files live: the per-user state directory, or the temp directory
+fn recovery_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .unwrap_or_else(std::env::temp_dir);
+    base.join("fluid_sim_paint").join("recovery")
+}
+
+fn lock_path(dir: &Path, session: &str) -> PathBuf {
+    dir.join(format!("{LOCK_PREFIX}{session}{LOCK_SUFFIX}"))
+}
+
+fn autosave_path(dir: &Path, session: &str) -> PathBuf {
+    dir.join(format!("autosave-{session}.fsp"))
+}
+
+// A crashed session whose autosave waits for the user's answer. Its lock stays held,
+// so another instance starting meanwhile doesn't offer the same file.
+struct PreviousSession {
+    autosave: PathBuf,
+    lock_path: PathBuf,
+    lock: File,
+}
+
+impl PreviousSession {
+    fn remove(self) {
+        let _ = std::fs::remove_file(&self.autosave);
+        // Left behind if it crashed while writing
+        let _ = std::fs::remove_file(self.autosave.with_extension("fsp.tmp"));
+        drop(self.lock);
+        let _ = std::fs::remove_file(&self.lock_path);
+    }
+}
+
+// The sim fields, copied into staging buffers with a frame's commands
+struct Capture {
+    width: u32,
+    height: u32,
+    params: GuiParams,
+    fields: Vec<(String, u32, StagedTexels)>, // Name, channels, texels
+}
+
+impl Capture {
+    // Runs on the writer thread, once every buffer is mapped
+    fn into_project(self) -> Project {
+        let fields = self
+            .fields
+            .into_iter()
+            .map(|(name, channels, staged)| ProjectField {
+                name,
+                channels,
+                data: staged.texels(),
+            })
+            .collect();
+        Project {
+            width: self.width,
+            height: self.height,
+            params: self.params,
+            fields,
+        }
+    }
+}
+
+// Mirrors DivergenceMonitor's readback: nothing here waits for the GPU or the disk
+enum Autosave {
+    Idle,
+    // The copies are recorded but not yet submitted
+    Recorded(Capture),
+    // map_async has been requested on every buffer; each one reports on the channel
+    Mapping {
+        capture: Capture,
+        receiver: Receiver<Result<(), BufferAsyncError>>,
+        remaining: usize,
+    },
+    Writing(JoinHandle<anyhow::Result<()>>),
+}
+
+// Autosave and crash recovery.
+// Every session has its own id, autosave file and lock file, and holds an OS lock on the
+// latter for as long as it runs: the OS drops the lock when the process dies, however it dies.
+// A lock file nobody holds is therefore a session that crashed, was killed or lost its GPU;
+// if it left an autosave, the user is offered to restore it. A clean exit removes both files.
+// A restored autosave is kept (and its lock held) until this session's own first autosave is
+// in place, so crashing again straight after a restore still has something to offer.
+// Everything here only logs on failure: recovery must never take the painting down with it.
+pub struct Recovery {
+    dir: Option<PathBuf>, // None: disabled (no file system, e.g. on the web)
+    session: String,
+    lock: Option<File>,
+    previous_session: Option<PreviousSession>,
+    restored: Option<PreviousSession>,
+    last_save: Instant,
+    autosave: Autosave,
+}
+
+impl Recovery {
+    // Recovery in the per-user state directory (disabled on the web)
+    pub fn start() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            return Self::disabled();
+        }
+        Self::start_in(recovery_dir())
+    }
+
+    // Looks for a crashed session in 'dir', then takes a lock for this one
+    pub fn start_in(dir: PathBuf) -> Self {
+        let mut recovery = Self::disabled();
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            log::warn!("Autosave disabled, can't create {}: {e}", dir.display());
+            return recovery;
+        }
+        recovery.previous_session = find_crashed_session(&dir);
+
+        let lock_path = lock_path(&dir, &recovery.session);
+        let lock = File::create(&lock_path)
+            .map_err(TryLockError::Error)
+            .and_then(|file| file.try_lock().map(|()| file));
+        match lock {
+            Ok(lock) => {
+                recovery.lock = Some(lock);
+                recovery.dir = Some(dir);
+            }
+            Err(e) => log::warn!("Autosave disabled, can't lock {}: {e}", lock_path.display()),
+        }
+        recovery
+    }
+
+    fn disabled() -> Self {
+        // The pid alone could come round again; with the start time it can't.
+        // The counter tells sessions of one process apart (only the tests have several).
+        static STARTED: AtomicU32 = AtomicU32::new(0);
+        let started = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let count = STARTED.fetch_add(1, Ordering::Relaxed);
+        Self {
+            dir: None,
+            session: format!("{}-{}-{count}", std::process::id(), started.as_millis()),
+            lock: None,
+            previous_session: None,
+            restored: None,
+            last_save: Instant::now(),
+            autosave: Autosave::Idle,
+        }
+    }
+
+    // The crashed session's autosave while the user hasn't decided what to do with it
+    pub fn previous_session(&self) -> Option<&Path> {
+        self.previous_session
+            .as_ref()
+            .map(|previous| previous.autosave.as_path())
+    }
+
+    // The user doesn't want the crashed session back: its files can go
+    pub fn discard_previous_session(&mut self) {
+        if let Some(previous) = self.previous_session.take() {
+            previous.remove();
+        }
+    }
+
+    // The crashed session's autosave was loaded. Its files go once this session has autosaved.
+    pub fn restored_previous_session(&mut self) {
+        if let Some(previous) = self.previous_session.take() {
+            self.restored = Some(previous);
+        }
+    }
+
+    // Where this session autosaves (None while disabled)
+    pub fn autosave_path(&self) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| autosave_path(dir, &self.session))
+    }
+
+    // True once 'interval' seconds have passed since the last autosave (0 = never), and the
+    // previous one is completely written
+    pub fn autosave_due(&self, interval: f32) -> bool {
+        // Also "never" for intervals no Duration can hold (inf, NaN)
+        let Ok(interval) = Duration::try_from_secs_f32(interval) else {
+            return false;
+        };
+        let idle = match &self.autosave {
+            Autosave::Idle => true,
+            Autosave::Writing(writer) => writer.is_finished(),
+            Autosave::Recorded(_) | Autosave::Mapping { .. } => false,
+        };
+        self.dir.is_some() && !interval.is_zero() && idle && self.last_save.elapsed() >= interval
+    }
+
+    // Records copies of 'fields' into staging buffers. Call when autosave_due(), before the
+    // encoder is submitted; after_submit() and poll() take it from there.
+    pub fn record_autosave(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        (width, height): (u32, u32),
+        params: &GuiParams,
+        fields: &[(&str, &Texture)],
+    ) {
+        self.collect_writer();
+        self.last_save = Instant::now();
+        let fields = fields
+            .iter()
+            .map(|(name, texture)| {
+                let channels = texture.texture.format().block_copy_size(None).unwrap_or(0) / 4;
+                (name.to_string(), channels, texture.stage(device, encoder))
+            })
+            .collect();
+        self.autosave = Autosave::Recorded(Capture {
+            width,
+            height,
+            params: params.clone(),
+            fields,
+        });
+    }
+
+    // Call once the encoder holding record_autosave() has been submitted
+    pub fn after_submit(&mut self) {
+        let Autosave::Recorded(capture) = std::mem::replace(&mut self.autosave, Autosave::Idle)
+        else {
+            return;
+        };
+        let (sender, receiver) = mpsc::channel();
+        for (_, _, staged) in &capture.fields {
+            let sender = sender.clone();
+            staged
+                .buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = sender.send(result);
+                });
+        }
+        let remaining = capture.fields.len();
+        self.autosave = Autosave::Mapping {
+            capture,
+            receiver,
+            remaining,
+        };
+    }
+
+    // Non-blocking: once every buffer is mapped, hands the capture to a background thread that
+    // unpacks it and writes the file (to a temporary file first, then renamed, so a crash
+    // mid-write leaves the previous autosave intact)
+    pub fn poll(&mut self, device: &Device) {
+        if let Autosave::Writing(writer) = &self.autosave
+            && writer.is_finished()
+        {
+            self.collect_writer();
+        }
+        let Autosave::Mapping {
+            receiver,
+            remaining,
+            ..
+        } = &mut self.autosave
+        else {
+            return;
+        };
+        let _ = device.poll(wgpu::PollType::Poll);
+
+        loop {
+            match receiver.try_recv() {
+                Ok(Ok(())) => *remaining -= 1,
+                Ok(Err(e)) => {
+                    log::warn!("Autosave readback failed: {e}");
+                    self.autosave = Autosave::Idle;
+                    return;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    log::warn!("Autosave readback was dropped");
+                    self.autosave = Autosave::Idle;
+                    return;
+                }
+            }
+        }
+        if *remaining > 0 {
+            return;
+        }
+
+        let (Autosave::Mapping { capture, .. }, Some(target)) = (
+            std::mem::replace(&mut self.autosave, Autosave::Idle),
+            self.autosave_path(),
+        ) else {
+            return;
+        };
+        let temp = target.with_extension("fsp.tmp");
+        self.autosave = Autosave::Writing(std::thread::spawn(move || {
+            save_project(&temp, &capture.into_project())?;
+            std::fs::rename(&temp, &target)?;
+            log::info!("Autosaved to {}", target.display());
+            Ok(())
+        }));
+    }
+
+    // Clean exit: waits for a save being written, then removes this session's files.
+    // An unanswered recovery is left for the next start.
+    pub fn shutdown(&mut self) {
+        self.collect_writer();
+        self.autosave = Autosave::Idle; // A capture still on the GPU is dropped
+        self.previous_session = None;
+        if let Some(restored) = self.restored.take() {
+            restored.remove();
+        }
+        let (Some(dir), Some(lock)) = (self.dir.take(), self.lock.take()) else {
+            return;
+        };
+        let _ = std::fs::remove_file(autosave_path(&dir, &self.session));
+        drop(lock);
+        let _ = std::fs::remove_file(lock_path(&dir, &self.session));
+    }
+
+    // Waits for the last background save and reports how it went. The first one that lands
+    // replaces a restored autosave.
+    fn collect_writer(&mut self) {
+        let Autosave::Writing(writer) = std::mem::replace(&mut self.autosave, Autosave::Idle)
+        else {
+            return;
+        };
+        match writer.join() {
+            Ok(Ok(())) => {
+                if let Some(restored) = self.restored.take() {
+                    restored.remove();
+                }
+            }
+            Ok(Err(e)) => log::warn!("Autosave failed: {e:#}"),
+            Err(_) => log::warn!("Autosave thread panicked"),
+        }
+    }
+}
+
+// The most recent autosave of a session whose lock nobody holds any more. Crashed sessions
+// that never autosaved just have their lock file removed; older autosaves are left for later.
+fn find_crashed_session(dir: &Path) -> Option<PreviousSession> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!("Can't look for crashed sessions in {}: {e}", dir.display());
+            return None;
+        }
+    };
+
+    let mut newest: Option<(SystemTime, PreviousSession)> = None;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(session) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(LOCK_PREFIX))
+            .and_then(|name| name.strip_suffix(LOCK_SUFFIX))
+        else {
+            continue;
+        };
+        let lock_path = entry.path();
+        let Ok(lock) = OpenOptions::new().write(true).open(&lock_path) else {
+            continue;
+        };
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => continue, // A running instance
+            Err(TryLockError::Error(e)) => {
+                log::warn!("Can't check {}: {e}", lock_path.display());
+                continue;
+            }
+        }
+
+        let autosave = autosave_path(dir, session);
+        let Ok(saved) = autosave.metadata().and_then(|meta| meta.modified()) else {
+            drop(lock);
+            let _ = std::fs::remove_file(&lock_path);
+            continue;
+        };
+        if newest.as_ref().is_none_or(|(time, _)| saved > *time) {
+            let previous = PreviousSession {
+                autosave,
+                lock_path,
+                lock,
+            };
+            newest = Some((saved, previous));
+        }
+    }
+    let (_, previous) = newest?;
+    log::warn!(
+        "The last session didn't shut down cleanly, its autosave is {}",
+        previous.autosave.display()
+    );
+    Some(previous)
+}
@@ -0,0 +1,63 @@
+use crate::gui_mod::gui::StabilizerMode;
+
+// Longest string (screen pixels) and slowest time constant (seconds), at strength 1.0
+const MAX_STRING_LENGTH: f32 = 80.0;
+const MAX_TIME_CONSTANT: f32 = 0.25;
+
+// Smooths the brush path before it reaches the sim. Jittery, zig-zagging strokes push the
+// liquid sideways in alternating directions, which is what rolls up into wavy
+// Kelvin–Helmholtz edges on fast strokes.
+// Works in SCREEN space, so the feel doesn't change with the zoom.
+#[derive(Default)]
+pub struct StrokeStabilizer {
+    pos: Option<[f32; 2]>, // Smoothed brush position while a stroke is in progress
+}
+
+impl StrokeStabilizer {
+    // Returns where the brush is this frame. 'target' is the raw mouse position, 'elapsed' the
+    // real seconds since the last call. While the button is up the brush sits on the mouse,
+    // so every stroke starts exactly where it was clicked.
+    pub fn update(
+        &mut self,
+        target: [f32; 2],
+        pressed: bool,
+        mode: StabilizerMode,
+        strength: f32,
+        elapsed: f32,
+    ) -> [f32; 2] {
+        let pos = match self.pos {
+            Some(pos) if pressed => pos,
+            _ => target,
+        };
+        let offset = [target[0] - pos[0], target[1] - pos[1]];
+
+        let next = match mode {
+            StabilizerMode::Off => target,
+            // Pulled string: the brush only moves once the mouse is further away than the
+            // string is long, and then just enough to keep it taut. Corners get rounded off.
+            StabilizerMode::LazyMouse => {
+                let string = strength * MAX_STRING_LENGTH;
+                let distance = offset[0].hypot(offset[1]);
+                if distance <= string {
+                    pos
+                } else {
+                    let pull = (distance - string) / distance;
+                    [pos[0] + offset[0] * pull, pos[1] + offset[1] * pull]
+                }
+            }
+            // Exponential smoothing with a time constant, so it doesn't depend on the frame rate
+            StabilizerMode::Smooth => {
+                let tau = strength * MAX_TIME_CONSTANT;
+                let follow = if tau > 0.0 {
+                    1.0 - (-elapsed / tau).exp()
+                } else {
+                    1.0
+                };
+                [pos[0] + offset[0] * follow, pos[1] + offset[1] * follow]
+            }
+        };
+
+        self.pos = pressed.then_some(next);
+        next
+    }
+}
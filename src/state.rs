@@ -1,33 +1,73 @@
 use super::canvas_mod::canvas::Canvas; // Import your new object
-use crate::gui_mod::gui::Gui;
+use crate::canvas_mod::grain::{generate_grain, paper_shade};
+use crate::canvas_mod::pigment::{
+    palette_cell_to_pigment, palette_cell_to_rgb, pigment_cell_to_rgb, rgb_cell_to_pigment,
+};
+use crate::gui_mod::gui::{
+    ColorModel, FileRequest, GrainAction, Gui, GuiParams, HistoryAction, MaskAction, RecoveryAction,
+};
+use crate::io_mod::export::{flatten_layers, save_png, save_raw_fields};
+use crate::io_mod::import::{
+    MaskPlacement, load_grain_image, load_ink_image, load_mask_image, luminance_velocity_seed,
+    place_mask,
+};
+use crate::io_mod::project::{Project, ProjectField, load_project, save_project};
+use crate::recovery::Recovery;
+use crate::stabilizer::StrokeStabilizer;
+use crate::timestep::{FixedTimestep, SIM_DT};
 use crate::wgpu_utils::wgpu_init;
 use std::iter;
 use std::sync::Arc;
-use winit::event::{ElementState, MouseButton};
+use std::time::Instant;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta};
+use winit::keyboard::ModifiersState;
 use winit::{
     event_loop::ActiveEventLoop,
     keyboard::KeyCode,
     window::{Fullscreen, Window},
 };
 
+const ROTATION_STEP_DEGREES: f32 = 15.0; // Per press of 4 / 6
+
 pub struct InteractionState {
-    pub mouse_pos: [f32; 2],
-    pub last_mouse_pos: [f32; 2],
+    pub mouse_pos: [f32; 2], // Raw cursor position (screen pixels)
+    // Every cursor position reported since the last frame, oldest first
+    pub cursor_samples: Vec<[f32; 2]>,
+    // Stabilized brush path since the last step ran. Starts where that step's stroke ended.
+    pub stroke: Vec<[f32; 2]>,
     pub mouse_pressed: bool,
     pub clear_requested: bool,
+    // Dragging the view (middle button, or Space + left button) instead of painting
+    pub panning: bool,
+    pub space_held: bool,
+    pub modifiers: ModifiersState,
+    // Set when the button goes down; the next frame snapshots the sim for undo before painting
+    pub stroke_started: bool,
 }
 
 impl Default for InteractionState {
     fn default() -> Self {
         Self {
             mouse_pos: [0.0, 0.0],
-            last_mouse_pos: [0.0, 0.0],
+            cursor_samples: Vec::new(),
+            stroke: Vec::new(),
             mouse_pressed: false,
             clear_requested: false,
+            panning: false,
+            space_held: false,
+            modifiers: ModifiersState::empty(),
+            stroke_started: false,
         }
     }
 }
 
+// An imported obstacle mask that is still being positioned
+struct PendingMask {
+    image: image::GrayAlphaImage,
+    // Placement and grid size the preview was last drawn for
+    shown: Option<(MaskPlacement, u32, u32)>,
+}
+
 pub struct State {
     surface: wgpu::Surface<'static>,
     device: wgpu::Device,
@@ -40,14 +80,28 @@ pub struct State {
     gui: Gui,
     canvas: Canvas,          // <--- The Engine
     input: InteractionState, // <--- The User
+    stabilizer: StrokeStabilizer,
+    recovery: Recovery,
+
+    pending_mask: Option<PendingMask>,
+    // What the density field currently holds. Lags the GUI setting until it is converted.
+    color_model: ColorModel,
+
+    // Real time -> sim steps
+    timestep: FixedTimestep,
+    last_frame: Instant,
 }
 
 impl State {
     pub async fn new(window: Arc<Window>) -> anyhow::Result<State> {
-        let (surface, device, queue, config) = wgpu_init(window.clone()).await;
+        let (surface, device, queue, config) = wgpu_init(window.clone()).await?;
 
         // 1. Init GUI
-        let gui = Gui::new(&window, &device, config.format);
+        let mut gui = Gui::new(&window, &device, config.format);
+
+        // Crash recovery: ask about the last session's autosave if it didn't exit cleanly
+        let recovery = Recovery::start();
+        gui.recovery_pending = recovery.previous_session().is_some();
 
         // 2. Init Canvas (The Sim)
         // Notice how we just ask for a "New Canvas" and give it the specs.
@@ -57,7 +111,7 @@ impl State {
             &config,
             gui.params.canvas_width,
             gui.params.canvas_height,
-            gui.params.zoom_level,
+            gui.params.view.zoom,
         );
 
         // 3. Init Input
@@ -70,21 +124,76 @@ impl State {
             config,
             is_surface_configured: false,
             window,
+            // Before 'gui' is moved in. The fresh canvas is empty, so it holds either.
+            color_model: gui.params.color_model,
             gui,
             canvas,
             input,
+            stabilizer: StrokeStabilizer::default(),
+            recovery,
+            pending_mask: None,
+            timestep: FixedTimestep::default(),
+            last_frame: Instant::now(),
         })
     }
 
     // Input handlers just update 'self.input'
     pub fn handle_mouse(&mut self, pos: [f32; 2]) {
+        if self.input.panning {
+            let last = self.input.mouse_pos;
+            self.gui
+                .params
+                .view
+                .pan_by([pos[0] - last[0], pos[1] - last[1]]);
+        }
         self.input.mouse_pos = pos;
+        self.input.cursor_samples.push(pos);
     }
 
     pub fn handle_click(&mut self, state: ElementState, button: MouseButton) {
-        if button == MouseButton::Left {
-            self.input.mouse_pressed = state == ElementState::Pressed;
+        let pressed = state == ElementState::Pressed;
+        match button {
+            // Space + left drag pans instead of painting
+            MouseButton::Left if pressed && self.input.space_held && !self.gui.wants_pointer() => {
+                self.input.panning = true;
+            }
+            MouseButton::Left => {
+                self.input.mouse_pressed = pressed;
+                if pressed {
+                    // Clicks on the panel aren't strokes worth an undo step
+                    self.input.stroke_started |= !self.gui.wants_pointer();
+                } else {
+                    self.input.panning = false;
+                }
+            }
+            MouseButton::Middle => {
+                self.input.panning = pressed && !self.gui.wants_pointer();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn handle_modifiers(&mut self, modifiers: ModifiersState) {
+        self.input.modifiers = modifiers;
+    }
+
+    // Mouse wheel: zoom in or out around the cursor
+    pub fn handle_wheel(&mut self, delta: MouseScrollDelta) {
+        const ZOOM_STEP: f32 = 1.1; // Per wheel notch
+        const PIXELS_PER_NOTCH: f32 = 50.0; // Touchpads report pixels
+
+        if self.gui.wants_pointer() {
+            return;
         }
+        let notches = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_NOTCH,
+        };
+        self.gui.params.view.zoom_at(
+            self.input.mouse_pos,
+            ZOOM_STEP.powf(notches),
+            (self.config.width, self.config.height),
+        );
     }
 
     pub fn handle_event(&mut self, event: &winit::event::WindowEvent) {
@@ -101,6 +210,11 @@ impl State {
     }
 
     pub fn handle_key(&mut self, event_loop: &ActiveEventLoop, key: KeyCode, pressed: bool) {
+        // Held keys: releases matter too
+        if key == KeyCode::Space {
+            self.input.space_held = pressed;
+            return;
+        }
         if !pressed {
             return;
         }
@@ -115,6 +229,31 @@ impl State {
             KeyCode::Delete => {
                 self.input.clear_requested = true;
             }
+            KeyCode::Home => {
+                self.gui.fit_requested = true;
+            }
+            // Ctrl+Z: undo, Ctrl+Shift+Z or Ctrl+Y: redo (Cmd on macOS). Text fields keep theirs.
+            KeyCode::KeyZ | KeyCode::KeyY
+                if (self.input.modifiers.control_key() || self.input.modifiers.super_key())
+                    && !self.gui.wants_keyboard() =>
+            {
+                let redo = key == KeyCode::KeyY || self.input.modifiers.shift_key();
+                self.gui.history_action = Some(if redo {
+                    HistoryAction::Redo
+                } else {
+                    HistoryAction::Undo
+                });
+            }
+            // Turn the paper in 15 degree steps, or back upright
+            KeyCode::Digit4 | KeyCode::Digit6 | KeyCode::Digit5 if !self.gui.wants_keyboard() => {
+                let step = ROTATION_STEP_DEGREES.to_radians();
+                let view = &mut self.gui.params.view;
+                match key {
+                    KeyCode::Digit4 => view.rotate_by(-step),
+                    KeyCode::Digit6 => view.rotate_by(step),
+                    _ => view.rotation = 0.0,
+                }
+            }
             _ => {}
         }
     }
@@ -130,12 +269,92 @@ impl State {
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
+        self.sync_color_model();
+
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
             });
 
+        // RESIZE CANVAS (only once the user has finished editing the dimensions)
+        if !self.gui.editing_canvas_size {
+            self.canvas.resize_sim(
+                &self.device,
+                &mut encoder,
+                self.gui.params.canvas_width,
+                self.gui.params.canvas_height,
+            );
+        }
+
+        // UNDO / REDO
+        match self.gui.history_action.take() {
+            Some(HistoryAction::Undo) => {
+                self.canvas
+                    .undo(&self.device, &mut encoder, &self.gui.params);
+            }
+            Some(HistoryAction::Redo) => {
+                self.canvas
+                    .redo(&self.device, &mut encoder, &self.gui.params);
+            }
+            None => {}
+        }
+
+        if std::mem::take(&mut self.gui.clear_walls_requested) {
+            self.canvas
+                .snapshot(&self.device, &mut encoder, &self.gui.params);
+            self.canvas.sim.clear_obstacles(&mut encoder);
+        }
+
+        if std::mem::take(&mut self.gui.fit_requested) {
+            self.gui.params.view.fit(
+                (self.config.width, self.config.height),
+                (self.canvas.sim.width, self.canvas.sim.height),
+            );
+        }
+
+        // TIMING: Measure the real frame time and turn it into whole sim steps
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        // STROKE: every cursor sample of the frame goes through the stabilizer, in order.
+        // A still mouse still counts once, so the smoothed brush keeps catching up.
+        let mut samples = std::mem::take(&mut self.input.cursor_samples);
+        if samples.is_empty() {
+            samples.push(self.input.mouse_pos);
+        }
+        let sample_time = elapsed / samples.len() as f32;
+        for sample in samples {
+            let pos = self.stabilizer.update(
+                sample,
+                self.input.mouse_pressed,
+                self.gui.params.stabilizer,
+                self.gui.params.stabilizer_strength,
+                sample_time,
+            );
+            // Hovering doesn't build a path: a stroke starts where the button went down
+            if !self.input.mouse_pressed {
+                self.input.stroke.clear();
+            }
+            if self.input.stroke.last() != Some(&pos) {
+                self.input.stroke.push(pos);
+            }
+        }
+
+        let substeps = if self.gui.params.paused {
+            self.timestep.reset();
+            u32::from(std::mem::take(&mut self.gui.step_requested))
+        } else {
+            self.timestep.advance(elapsed)
+        };
+
+        // Remember the painting before a new stroke or a clear, so it can be undone
+        if std::mem::take(&mut self.input.stroke_started) || self.input.clear_requested {
+            self.canvas
+                .snapshot(&self.device, &mut encoder, &self.gui.params);
+        }
+
         // UPDATE CANVAS (Physics & Input)
         self.canvas.update(
             &self.queue,
@@ -143,6 +362,8 @@ impl State {
             &self.input,
             &self.gui.params,
             (self.config.width, self.config.height),
+            substeps,
+            SIM_DT * self.gui.params.time_scale,
         );
         self.input.clear_requested = false;
 
@@ -170,11 +391,387 @@ impl State {
             screen_descriptor,
         );
 
+        // AUTOSAVE: the fields are copied with this frame's commands; reading them back and
+        // writing the file happen over the next frames, off this thread
+        if self
+            .recovery
+            .autosave_due(self.gui.params.autosave_interval)
+        {
+            let sim = &self.canvas.sim;
+            self.recovery.record_autosave(
+                &self.device,
+                &mut encoder,
+                (sim.width, sim.height),
+                &self.gui.params,
+                &sim.project_fields(),
+            );
+        }
+
         // Cleanup
-        self.input.last_mouse_pos = self.input.mouse_pos;
+        // Frames that ran no step keep their points, so the next step paints the whole
+        // path. While paused, mouse movement is dropped instead of piling up.
+        if substeps > 0 || self.gui.params.paused {
+            let end = self.input.stroke.len().saturating_sub(1);
+            self.input.stroke.drain(..end);
+        }
         self.queue.submit(iter::once(encoder.finish()));
         output.present();
 
+        self.canvas.sim.after_submit(&self.device);
+        self.recovery.after_submit();
+        self.gui.divergence_stats = self.canvas.sim.divergence_stats();
+        self.gui.history_steps = self.canvas.history_steps();
+
+        if let Some(request) = self.gui.file_request.take()
+            && let Err(e) = self.handle_file_request(request)
+        {
+            log::error!("File operation failed: {e:#}");
+        }
+        self.update_pending_mask();
+        self.update_grain();
+        self.update_recovery();
+
+        Ok(())
+    }
+
+    // Clean exit: the recovery files aren't needed any more
+    pub fn shutdown(&mut self) {
+        self.recovery.shutdown();
+    }
+
+    // Answers the restore prompt, and moves a running autosave along
+    fn update_recovery(&mut self) {
+        if let Some(action) = self.gui.recovery_action.take() {
+            if let (RecoveryAction::Restore, Some(path)) =
+                (action, self.recovery.previous_session())
+            {
+                // On failure the files stay and the prompt stays open: they're the only copy
+                match load_project(path).and_then(|project| self.open_project(project)) {
+                    Ok(()) => {
+                        log::info!("Restored the previous session");
+                        self.recovery.restored_previous_session();
+                    }
+                    Err(e) => {
+                        log::error!("Couldn't restore the previous session: {e:#}");
+                        self.gui.recovery_error = Some(format!("{e:#}"));
+                    }
+                }
+            } else {
+                self.recovery.discard_previous_session();
+            }
+            self.gui.recovery_pending = self.recovery.previous_session().is_some();
+        }
+
+        self.recovery.poll(&self.device);
+    }
+
+    // Switching between RGB and pigment mixing converts the painting so it looks the same.
+    // Leaving palette mode does too; entering it starts on clean paper, since an arbitrary
+    // colour can't be split back into the eight pigments.
+    // Runs before the frame is recorded: the readback waits for the previous frames only.
+    fn sync_color_model(&mut self) {
+        let target = self.gui.params.color_model;
+        if target == self.color_model {
+            return;
+        }
+        // Snapshots hold the fields in the old model
+        self.canvas.clear_history();
+        let sim = &self.canvas.sim;
+        if target == ColorModel::Palette {
+            let empty = vec![[0.0; 4]; (sim.width * sim.height) as usize];
+            sim.upload_density(&self.queue, &empty);
+            sim.upload_density2(&self.queue, &empty);
+            sim.upload_paper(&self.queue, &empty);
+            sim.upload_paper2(&self.queue, &empty);
+            log::info!("Switched to palette mixing: the canvas starts clean");
+            self.color_model = target;
+            return;
+        }
+        let convert = |rgb: Vec<[f32; 4]>| -> Vec<[f32; 4]> {
+            match target {
+                ColorModel::Pigment => rgb.into_iter().map(rgb_cell_to_pigment).collect(),
+                _ => rgb,
+            }
+        };
+        let [fluid, paper] = self.read_rgb_layers();
+        sim.upload_density(&self.queue, &convert(fluid));
+        sim.upload_paper(&self.queue, &convert(paper));
+        self.color_model = target;
+    }
+
+    // The liquid ink and the settled ink as RGB cells (colour + amount), whatever the fields hold
+    fn read_rgb_layers(&self) -> [Vec<[f32; 4]>; 2] {
+        let sim = &self.canvas.sim;
+        let (device, queue) = (&self.device, &self.queue);
+        let fluid = sim.read_density(device, queue);
+        let paper = sim.read_paper(device, queue);
+        match self.color_model {
+            ColorModel::Rgb => [fluid, paper],
+            ColorModel::Pigment => {
+                [fluid, paper].map(|cells| cells.into_iter().map(pigment_cell_to_rgb).collect())
+            }
+            ColorModel::Palette => {
+                let to_rgb = |low: Vec<[f32; 4]>, high: Vec<[f32; 4]>| {
+                    low.into_iter()
+                        .zip(high)
+                        .map(|(low, high)| palette_cell_to_rgb(low, high))
+                        .collect()
+                };
+                [
+                    to_rgb(fluid, sim.read_density2(device, queue)),
+                    to_rgb(paper, sim.read_paper2(device, queue)),
+                ]
+            }
+        }
+    }
+
+    // The whole painting (liquid over paper) as cells composite_pixel understands in the
+    // returned model. Palette mode goes through the equivalent K/S cells, which look the same.
+    fn read_flattened(&self) -> (Vec<[f32; 4]>, ColorModel) {
+        let sim = &self.canvas.sim;
+        let (device, queue) = (&self.device, &self.queue);
+        let model = self.color_model;
+        let flatten = |fluid: Vec<[f32; 4]>, paper: Vec<[f32; 4]>| -> Vec<[f32; 4]> {
+            fluid
+                .into_iter()
+                .zip(paper)
+                .map(|(fluid, paper)| flatten_layers(fluid, paper, model))
+                .collect()
+        };
+        let low = flatten(
+            sim.read_density(device, queue),
+            sim.read_paper(device, queue),
+        );
+        if model != ColorModel::Palette {
+            return (low, model);
+        }
+        let high = flatten(
+            sim.read_density2(device, queue),
+            sim.read_paper2(device, queue),
+        );
+        let pigment = low
+            .into_iter()
+            .zip(high)
+            .map(|(low, high)| palette_cell_to_pigment(low, high))
+            .collect();
+        (pigment, ColorModel::Pigment)
+    }
+
+    // Paper colour under every cell, shaded by the grain like fs_main does
+    fn read_paper_colors(&self) -> Vec<[f32; 3]> {
+        let params = &self.gui.params;
+        self.canvas
+            .sim
+            .read_grain(&self.device, &self.queue)
+            .into_iter()
+            .map(|relief| paper_shade(params.paper_color, relief, params.grain_shading))
+            .collect()
+    }
+
+    // Generates or removes the paper grain when asked to
+    fn update_grain(&mut self) {
+        let Some(action) = self.gui.grain_action.take() else {
+            return;
+        };
+        let sim = &self.canvas.sim;
+        let relief = match action {
+            GrainAction::Generate => generate_grain(
+                sim.width,
+                sim.height,
+                self.gui.params.grain_scale,
+                self.gui.grain_seed,
+            ),
+            GrainAction::Flat => vec![0.0; (sim.width * sim.height) as usize],
+        };
+        sim.upload_grain(&self.queue, &relief);
+    }
+
+    // Redraws the preview when the placement (or the grid) changed, and applies or drops the
+    // mask when asked to. Applied masks are added to the walls already painted.
+    fn update_pending_mask(&mut self) {
+        let action = self.gui.mask_action.take();
+        let Some(pending) = &mut self.pending_mask else {
+            self.gui.mask_pending = false;
+            return;
+        };
+        let placement = self.gui.mask_placement;
+
+        match action {
+            Some(MaskAction::Apply) => {
+                // Undoable like a stroke: the snapshot is submitted before the upload
+                let mut encoder =
+                    self.device
+                        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                            label: Some("Apply Mask Encoder"),
+                        });
+                self.canvas
+                    .snapshot(&self.device, &mut encoder, &self.gui.params);
+                self.queue.submit(iter::once(encoder.finish()));
+
+                let sim = &self.canvas.sim;
+                let placed = place_mask(&pending.image, &placement, sim.width, sim.height);
+                let mut obstacles = sim.read_obstacles(&self.device, &self.queue);
+                for (cell, mask) in obstacles.iter_mut().zip(&placed) {
+                    *cell = cell.max(*mask);
+                }
+                sim.upload_obstacles(&self.queue, &obstacles);
+                self.pending_mask = None;
+                self.canvas.set_mask_preview(&self.queue, None);
+            }
+            Some(MaskAction::Cancel) => {
+                self.pending_mask = None;
+                self.canvas.set_mask_preview(&self.queue, None);
+            }
+            None => {
+                let sim = &self.canvas.sim;
+                let current = Some((placement, sim.width, sim.height));
+                if pending.shown != current {
+                    let placed = place_mask(&pending.image, &placement, sim.width, sim.height);
+                    self.canvas.set_mask_preview(&self.queue, Some(&placed));
+                    pending.shown = current;
+                }
+            }
+        }
+        self.gui.mask_pending = self.pending_mask.is_some();
+    }
+
+    // Saves read the current fields back from the GPU; imports upload straight into the sim.
+    fn handle_file_request(&mut self, request: FileRequest) -> anyhow::Result<()> {
+        let sim = &self.canvas.sim;
+        match request {
+            FileRequest::SavePng(path) => {
+                let (density, color_model) = self.read_flattened();
+                let paper = self.read_paper_colors();
+                save_png(&path, sim.width, sim.height, &density, &paper, color_model)?;
+                log::info!("Saved {}", path.display());
+            }
+            FileRequest::SaveRawFields(path) => {
+                let density = sim.read_density(&self.device, &self.queue);
+                let velocity = sim.read_velocity(&self.device, &self.queue);
+                save_raw_fields(&path, sim.width, sim.height, &density, &velocity)?;
+                log::info!("Saved raw fields next to {}", path.display());
+            }
+            FileRequest::ImportImage {
+                path,
+                seed_velocity,
+            } => {
+                let ink = load_ink_image(&path, sim.width, sim.height)?;
+                match self.color_model {
+                    ColorModel::Rgb => sim.upload_density(&self.queue, &ink),
+                    ColorModel::Pigment => {
+                        let pigment: Vec<[f32; 4]> =
+                            ink.iter().copied().map(rgb_cell_to_pigment).collect();
+                        sim.upload_density(&self.queue, &pigment);
+                    }
+                    ColorModel::Palette => {
+                        anyhow::bail!(
+                            "images can't be imported in palette mode, switch mixing to RGB or Pigment first"
+                        )
+                    }
+                }
+                if seed_velocity {
+                    let velocity = luminance_velocity_seed(&ink, sim.width, sim.height, 200.0);
+                    sim.upload_velocity(&self.queue, &velocity);
+                }
+                log::info!("Imported {}", path.display());
+            }
+            FileRequest::ImportMask(path) => {
+                self.pending_mask = Some(PendingMask {
+                    image: load_mask_image(&path)?,
+                    shown: None,
+                });
+                log::info!("Loaded mask {} (place it, then apply)", path.display());
+            }
+            FileRequest::ImportGrain(path) => {
+                let relief = load_grain_image(&path, sim.width, sim.height)?;
+                sim.upload_grain(&self.queue, &relief);
+                log::info!("Loaded paper grain {}", path.display());
+            }
+            FileRequest::SaveProject(path) => {
+                save_project(&path, &self.snapshot_project())?;
+                log::info!("Saved project {}", path.display());
+            }
+            FileRequest::OpenProject(path) => {
+                self.open_project(load_project(&path)?)?;
+                log::info!("Opened project {}", path.display());
+            }
+        }
+        Ok(())
+    }
+
+    // Reads the live sim back into a project (blocks until the GPU is done)
+    fn snapshot_project(&self) -> Project {
+        let sim = &self.canvas.sim;
+        let fields = sim
+            .project_fields()
+            .into_iter()
+            .map(|(name, texture)| {
+                let channels = texture.texture.format().block_copy_size(None).unwrap_or(0) / 4;
+                ProjectField {
+                    name: name.to_string(),
+                    channels,
+                    data: texture.read_back(&self.device, &self.queue),
+                }
+            })
+            .collect();
+        Project {
+            width: sim.width,
+            height: sim.height,
+            params: self.gui.params.clone(),
+            fields,
+        }
+    }
+
+    // Replaces the painting, the settings and the view with the project's.
+    // Fields the file doesn't have (or has in another shape) start empty.
+    // A canvas bigger than this GPU's textures is refused before anything is replaced.
+    fn open_project(&mut self, project: Project) -> anyhow::Result<()> {
+        let max_size = self.device.limits().max_texture_dimension_2d;
+        anyhow::ensure!(
+            project.width <= max_size && project.height <= max_size,
+            "the {} x {} canvas is larger than this GPU supports ({max_size})",
+            project.width,
+            project.height
+        );
+
+        // The app preferences stay as they are; they aren't the painting's
+        self.gui.params = GuiParams {
+            undo_budget_mb: self.gui.params.undo_budget_mb,
+            autosave_interval: self.gui.params.autosave_interval,
+            ..project.params.clone()
+        };
+        // The fields are stored in the saved colour model: nothing to convert
+        self.color_model = self.gui.params.color_model;
+        self.pending_mask = None;
+
+        // The resize has to run before the uploads, which land ahead of any later encoder
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Open Project Encoder"),
+            });
+        self.canvas
+            .resize_sim(&self.device, &mut encoder, project.width, project.height);
+        self.canvas.set_mask_preview(&self.queue, None);
+        self.canvas.clear_history();
+        self.queue.submit(iter::once(encoder.finish()));
+
+        let cells = project.width as usize * project.height as usize;
+        for (name, texture) in self.canvas.sim.project_fields() {
+            let channels = texture.texture.format().block_copy_size(None).unwrap_or(0) / 4;
+            match project.field(name) {
+                Some(field) if field.channels == channels => {
+                    texture.write_all(&self.queue, bytemuck::cast_slice(&field.data));
+                }
+                other => {
+                    if other.is_some() {
+                        log::warn!("Project field '{name}' has the wrong channel count, cleared");
+                    }
+                    let zeros = vec![0.0f32; cells * channels as usize];
+                    texture.write_all(&self.queue, bytemuck::cast_slice(&zeros));
+                }
+            }
+        }
         Ok(())
     }
 }
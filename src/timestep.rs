@@ -0,0 +1,41 @@
+// Simulated seconds per sim step at time scale 1.0
+pub const SIM_DT: f32 = 1.0 / 60.0;
+
+// Fixed-timestep accumulator: turns real frame times into a whole number of sim steps,
+// so flow speed no longer depends on the monitor's refresh rate.
+pub struct FixedTimestep {
+    pub step: f32, // Real seconds per sim step
+    // Upper bound on steps per frame. After a long stall (window drag, breakpoint) the
+    // backlog is dropped instead of being replayed all at once.
+    pub max_substeps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f32, max_substeps: u32) -> Self {
+        Self {
+            step,
+            max_substeps,
+            accumulator: 0.0,
+        }
+    }
+
+    // Adds 'elapsed' real seconds and returns how many sim steps to run this frame
+    pub fn advance(&mut self, elapsed: f32) -> u32 {
+        self.accumulator += elapsed;
+        let due = (self.accumulator / self.step).floor() as u32;
+        self.accumulator -= due as f32 * self.step;
+        due.min(self.max_substeps)
+    }
+
+    // Forgets any partial step (e.g. while paused)
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(SIM_DT, 4)
+    }
+}
@@ -1,24 +1,21 @@
+use anyhow::Context;
 use std::sync::Arc;
 use winit::window::Window;
 
 pub async fn wgpu_init(
     window: Arc<Window>,
-) -> (
+) -> anyhow::Result<(
     wgpu::Surface<'static>,
     wgpu::Device,
     wgpu::Queue,
     wgpu::SurfaceConfiguration,
-) {
+)> {
     let size = window.inner_size();
-    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-        #[cfg(not(target_arch = "wasm32"))]
-        backends: wgpu::Backends::PRIMARY,
-        #[cfg(target_arch = "wasm32")]
-        backends: wgpu::Backends::GL,
-        ..Default::default()
-    });
+    let instance = create_instance();
 
-    let surface = instance.create_surface(window.clone()).unwrap();
+    let surface = instance
+        .create_surface(window.clone())
+        .context("failed to create a surface for the window")?;
     let adapter = instance
         .request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::HighPerformance,
@@ -26,23 +23,11 @@ pub async fn wgpu_init(
             force_fallback_adapter: false,
         })
         .await
-        .unwrap();
+        .context("no graphics adapter can draw to this window")?;
 
-    let (device, queue) = adapter
-        .request_device(&wgpu::DeviceDescriptor {
-            label: None,
-            required_features: wgpu::Features::FLOAT32_FILTERABLE | wgpu::Features::CLEAR_TEXTURE,
-            experimental_features: wgpu::ExperimentalFeatures::disabled(),
-            required_limits: if cfg!(target_arch = "wasm32") {
-                wgpu::Limits::downlevel_webgl2_defaults()
-            } else {
-                wgpu::Limits::default()
-            },
-            memory_hints: Default::default(),
-            trace: wgpu::Trace::Off,
-        })
+    let (device, queue) = request_device(&adapter)
         .await
-        .unwrap();
+        .context("the graphics adapter refused the device (FLOAT32_FILTERABLE and CLEAR_TEXTURE are required)")?;
 
     let surface_caps = surface.get_capabilities(&adapter);
     let surface_format = surface_caps
@@ -50,7 +35,8 @@ pub async fn wgpu_init(
         .iter()
         .copied()
         .find(|f| f.is_srgb())
-        .unwrap_or(surface_caps.formats[0]);
+        .or_else(|| surface_caps.formats.first().copied())
+        .context("the surface supports no formats on this adapter")?;
 
     let config = wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -58,10 +44,69 @@ pub async fn wgpu_init(
         width: size.width,
         height: size.height,
         present_mode: wgpu::PresentMode::AutoVsync,
-        alpha_mode: surface_caps.alpha_modes[0],
+        alpha_mode: surface_caps
+            .alpha_modes
+            .first()
+            .copied()
+            .unwrap_or(wgpu::CompositeAlphaMode::Auto),
         view_formats: vec![],
         desired_maximum_frame_latency: 2,
     };
 
-    (surface, device, queue, config)
+    Ok((surface, device, queue, config))
+}
+
+// Headless: No window, no surface. Used by the headless runner (CI, scripted tests).
+// 'force_fallback_adapter' picks a software rasterizer when no GPU is present: lavapipe
+// through Vulkan, WARP through DX12, or llvmpipe through GL, which is added for it.
+pub async fn wgpu_init_headless(
+    force_fallback_adapter: bool,
+) -> anyhow::Result<(wgpu::Device, wgpu::Queue)> {
+    let instance = if force_fallback_adapter {
+        wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::PRIMARY | wgpu::Backends::GL,
+            ..Default::default()
+        })
+    } else {
+        create_instance()
+    };
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter,
+        })
+        .await?;
+    log::info!("Headless adapter: {:?}", adapter.get_info());
+
+    Ok(request_device(&adapter).await?)
+}
+
+fn create_instance() -> wgpu::Instance {
+    wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        #[cfg(not(target_arch = "wasm32"))]
+        backends: wgpu::Backends::PRIMARY,
+        #[cfg(target_arch = "wasm32")]
+        backends: wgpu::Backends::GL,
+        ..Default::default()
+    })
+}
+
+async fn request_device(
+    adapter: &wgpu::Adapter,
+) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+    adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: None,
+            required_features: wgpu::Features::FLOAT32_FILTERABLE | wgpu::Features::CLEAR_TEXTURE,
+            experimental_features: wgpu::ExperimentalFeatures::disabled(),
+            required_limits: if cfg!(target_arch = "wasm32") {
+                wgpu::Limits::downlevel_webgl2_defaults()
+            } else {
+                wgpu::Limits::default()
+            },
+            memory_hints: Default::default(),
+            trace: wgpu::Trace::Off,
+        })
+        .await
 }
@@ -0,0 +1,140 @@
+// PNG and PFM export (io_mod/export.rs): the sRGB curve, the composite against the CPU models it
+// copies (the paper shading in grain.rs, the palette mix in pigment.rs), and the PFM layout.
+use fluid_sim_paint::canvas_mod::grain::paper_shade;
+use fluid_sim_paint::canvas_mod::pigment::{palette_cell_to_pigment, palette_reflectance};
+use fluid_sim_paint::gui_mod::gui::ColorModel;
+use fluid_sim_paint::io_mod::export::{composite_pixel, linear_to_srgb8, save_raw_fields};
+
+fn assert_close(a: [f32; 3], b: [f32; 3], what: &str) {
+    for (x, y) in a.iter().zip(&b) {
+        assert!((x - y).abs() < 1e-5, "{what}: {a:?} vs {b:?}");
+    }
+}
+
+#[test]
+fn srgb_curve_hits_its_endpoints_and_midpoint() {
+    assert_eq!(linear_to_srgb8(0.0), 0);
+    assert_eq!(linear_to_srgb8(1.0), 255);
+    // Linear 0.5 is sRGB 0.7354, 187.52 of 255
+    assert_eq!(linear_to_srgb8(0.5), 188);
+    // Middle grey (sRGB 128) is about 21.6% linear
+    assert_eq!(linear_to_srgb8(0.2158), 128);
+    // The linear segment near black
+    assert_eq!(linear_to_srgb8(0.001), 3);
+    // Out-of-range values are clamped
+    assert_eq!(linear_to_srgb8(-0.5), 0);
+    assert_eq!(linear_to_srgb8(7.0), 255);
+}
+
+#[test]
+fn ink_is_blended_over_the_paper_by_density() {
+    let paper = [0.9, 0.8, 0.7];
+    let ink = [0.1, 0.2, 0.3];
+    let cell = |density: f32| [ink[0], ink[1], ink[2], density];
+
+    assert_close(
+        composite_pixel(cell(0.0), paper, ColorModel::Rgb),
+        paper,
+        "no ink",
+    );
+    assert_close(
+        composite_pixel(cell(1.0), paper, ColorModel::Rgb),
+        ink,
+        "full ink",
+    );
+    assert_close(
+        composite_pixel(cell(0.25), paper, ColorModel::Rgb),
+        [0.7, 0.65, 0.6],
+        "a quarter",
+    );
+
+    // Pigment mode filters the paper: K/S 0 is clean paper, a strong pigment darkens it
+    let pigment = composite_pixel([0.0, 0.5, 50.0, 1.0], paper, ColorModel::Pigment);
+    assert!(
+        (pigment[0] - paper[0]).abs() < 1e-5,
+        "clean channel: {pigment:?}"
+    );
+    assert!(
+        pigment[1] < paper[1] && pigment[2] < pigment[1],
+        "{pigment:?}"
+    );
+}
+
+#[test]
+fn blank_paper_exports_as_the_shaded_paper() {
+    let paper = [0.95, 0.92, 0.85];
+    for shading in [0.0, 0.4, 1.0] {
+        for relief in [-1.0, -0.3, 0.0, 0.6, 1.0] {
+            let shaded = paper_shade(paper, relief, shading);
+            // The expression fs_main shades the paper with
+            let factor = 1.0 + shading * 0.5 * f32::min(relief, 0.0);
+            assert_close(shaded, paper.map(|c| c * factor), "paper_shade");
+
+            for model in [ColorModel::Rgb, ColorModel::Pigment] {
+                let exported = composite_pixel([0.0; 4], shaded, model);
+                assert_close(exported, shaded, &format!("{model:?} at relief {relief}"));
+            }
+        }
+    }
+}
+
+#[test]
+fn palette_export_matches_the_palette_mix() {
+    let white = [1.0; 3];
+    let mixes = [
+        ([0.5, 0.0, 0.0, 0.0], [0.0, 0.5, 0.0, 0.0]),
+        ([0.1, 0.2, 0.0, 0.3], [0.0, 0.0, 0.2, 0.1]),
+        ([0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.25]),
+    ];
+    for (low, high) in mixes {
+        let concentrations = [
+            low[0], low[1], low[2], low[3], high[0], high[1], high[2], high[3],
+        ];
+        let cell = palette_cell_to_pigment(low, high);
+        let exported = composite_pixel(cell, white, ColorModel::Pigment);
+        assert_close(exported, palette_reflectance(concentrations), "palette mix");
+    }
+}
+
+// Header, then the pixels as little-endian floats
+fn read_pfm(path: &std::path::Path) -> (String, Vec<f32>) {
+    let bytes = std::fs::read(path).unwrap();
+    // Three header lines
+    let mut newlines = bytes.iter().enumerate().filter(|(_, b)| **b == b'\n');
+    let end = newlines.nth(2).unwrap().0 + 1;
+    let header = String::from_utf8(bytes[..end].to_vec()).unwrap();
+    let data = bytes[end..]
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    (header, data)
+}
+
+#[test]
+fn pfm_fields_are_stored_bottom_row_first() {
+    let dir = std::env::temp_dir().join(format!("fluid_sim_paint-export-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // 3 x 2 cells, top row first like the readbacks
+    let density: Vec<[f32; 4]> = (0..6)
+        .map(|i| [i as f32, 0.5, -1.0, 10.0 + i as f32])
+        .collect();
+    let velocity: Vec<[f32; 2]> = (0..6).map(|i| [i as f32, -(i as f32)]).collect();
+    save_raw_fields(&dir.join("canvas.png"), 3, 2, &density, &velocity).unwrap();
+
+    // Greyscale: "Pf", negative scale for little-endian
+    let (header, amount) = read_pfm(&dir.join("canvas_density.pfm"));
+    assert_eq!(header, "Pf\n3 2\n-1.0\n");
+    assert_eq!(amount, [13.0, 14.0, 15.0, 10.0, 11.0, 12.0]);
+
+    let (header, ink) = read_pfm(&dir.join("canvas_ink.pfm"));
+    assert_eq!(header, "PF\n3 2\n-1.0\n");
+    assert_eq!(ink.len(), 3 * 6);
+    assert_eq!(ink[..3], [3.0, 0.5, -1.0]); // First cell of the bottom row
+
+    let (header, vel) = read_pfm(&dir.join("canvas_velocity.pfm"));
+    assert_eq!(header, "PF\n3 2\n-1.0\n");
+    assert_eq!(vel[..6], [3.0, -3.0, 0.0, 4.0, -4.0, 0.0]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,34 @@
+// Real frame time -> whole sim steps (timestep.rs).
+use fluid_sim_paint::timestep::FixedTimestep;
+
+// A quarter second per step keeps the arithmetic exact
+const STEP: f32 = 0.25;
+
+#[test]
+fn partial_steps_carry_over() {
+    let mut timestep = FixedTimestep::new(STEP, 4);
+    assert_eq!(timestep.advance(0.125), 0);
+    // 0.125 left over + 0.375 = exactly two steps
+    assert_eq!(timestep.advance(0.375), 2);
+    assert_eq!(timestep.advance(0.125), 0);
+    assert_eq!(timestep.advance(0.125), 1);
+}
+
+#[test]
+fn a_stall_is_capped_and_dropped() {
+    let mut timestep = FixedTimestep::new(STEP, 4);
+    // Ten steps' worth of stall only runs the cap...
+    assert_eq!(timestep.advance(10.0 * STEP), 4);
+    // ...and the rest isn't replayed on the following frames
+    assert_eq!(timestep.advance(0.0), 0);
+    assert_eq!(timestep.advance(STEP), 1);
+}
+
+#[test]
+fn reset_forgets_the_partial_step() {
+    let mut timestep = FixedTimestep::new(STEP, 4);
+    assert_eq!(timestep.advance(0.2), 0);
+    timestep.reset();
+    assert_eq!(timestep.advance(0.2), 0);
+    assert_eq!(timestep.advance(0.05), 1);
+}
@@ -0,0 +1,78 @@
+// The headless runner: brush script parsing, and a few steps on whatever adapter is available.
+use fluid_sim_paint::gui_mod::gui::GuiParams;
+use fluid_sim_paint::headless::{BrushEvent, HeadlessSim, parse_script};
+
+#[test]
+fn parses_events_and_skips_comments() {
+    let script = "# a short stroke\n\
+                  0 10 20 30 20\n\
+                  \n\
+                  \t 3  30.5 20 -4e1 0.25 \n";
+    let events = parse_script(script).unwrap();
+    assert_eq!(
+        events,
+        [
+            BrushEvent {
+                step: 0,
+                from: [10.0, 20.0],
+                to: [30.0, 20.0],
+            },
+            BrushEvent {
+                step: 3,
+                from: [30.5, 20.0],
+                to: [-40.0, 0.25],
+            },
+        ]
+    );
+}
+
+#[test]
+fn reports_the_malformed_line() {
+    let wrong_count = parse_script("0 1 2 3 4\n# ok\n1 2 3 4\n").unwrap_err();
+    assert!(
+        format!("{wrong_count:#}").contains("line 3: expected 5 fields"),
+        "{wrong_count:#}"
+    );
+
+    let bad_number = parse_script("0 1 2 three 4").unwrap_err();
+    assert!(
+        format!("{bad_number:#}").contains("line 1: bad number 'three'"),
+        "{bad_number:#}"
+    );
+
+    // The step is a frame index, not a float
+    let bad_step = parse_script("1.5 1 2 3 4").unwrap_err();
+    assert!(
+        format!("{bad_step:#}").contains("line 1: bad step '1.5'"),
+        "{bad_step:#}"
+    );
+}
+
+#[test]
+fn rejects_two_segments_on_one_step() {
+    let twice = parse_script("2 0 0 1 1\n3 1 1 2 2\n# later\n2 5 5 6 6\n").unwrap_err();
+    assert!(
+        format!("{twice:#}").contains("line 4: step 2 already has a segment (line 1)"),
+        "{twice:#}"
+    );
+}
+
+#[test]
+#[ignore = "needs a graphics adapter (a software one is enough), run with --include-ignored"]
+fn a_scripted_stroke_leaves_ink() {
+    let mut headless = pollster::block_on(HeadlessSim::new(64, 64, true)).unwrap();
+
+    let script = parse_script("0 16 32 48 32\n1 48 32 48 40\n").unwrap();
+    let params = GuiParams::default();
+    headless.run(&params, 4, &script);
+    assert_eq!(headless.steps_taken, 4);
+
+    let density = headless.read_density();
+    assert_eq!(density.len(), 64 * 64);
+    assert!(density.iter().flatten().all(|value| value.is_finite()));
+    let total_ink: f32 = density.iter().map(|cell| cell[3]).sum();
+    assert!(total_ink > 0.0, "the stroke left no ink");
+
+    let velocity = headless.read_velocity();
+    assert!(velocity.iter().flatten().all(|value| value.is_finite()));
+}
@@ -0,0 +1,161 @@
+// Convergence of the CPU reference pressure solvers, and the GPU multigrid against them.
+use fluid_sim_paint::canvas_mod::poisson_reference::{
+    Grid, VCycleSettings, jacobi, red_black_sor, residual, v_cycle,
+};
+use fluid_sim_paint::gui_mod::gui::{GuiParams, PressureSolver};
+use fluid_sim_paint::headless::HeadlessSim;
+
+// A zero-mean right-hand side (compatible with the pure Neumann boundary), with both
+// smooth and sharp features like a brush stroke's divergence.
+fn stroke_like_rhs(width: usize, height: usize) -> Grid {
+    let mut rhs = Grid::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let u = x as f32 / width as f32;
+            let v = y as f32 / height as f32;
+            let smooth = (std::f32::consts::TAU * u).cos() * (std::f32::consts::PI * v).cos();
+            let sharp = if x == width / 3 && y > height / 4 && y < 3 * height / 4 {
+                1.0
+            } else if x == width / 3 + 1 && y > height / 4 && y < 3 * height / 4 {
+                -1.0
+            } else {
+                0.0
+            };
+            rhs.set(x, y, smooth + sharp);
+        }
+    }
+    rhs
+}
+
+#[test]
+fn v_cycle_beats_the_frames_jacobi_budget() {
+    let rhs = stroke_like_rhs(128, 96);
+    let initial = residual(&Grid::new(128, 96), &rhs, 1.0).rms();
+
+    // What the Jacobi path does every frame
+    let mut p_jacobi = Grid::new(128, 96);
+    jacobi(&mut p_jacobi, &rhs, 40);
+    let jacobi_residual = residual(&p_jacobi, &rhs, 1.0).rms();
+
+    let mut p_mg = Grid::new(128, 96);
+    let settings = VCycleSettings::default();
+    v_cycle(&mut p_mg, &rhs, &settings);
+    v_cycle(&mut p_mg, &rhs, &settings);
+    let mg_residual = residual(&p_mg, &rhs, 1.0).rms();
+
+    assert!(
+        mg_residual < jacobi_residual * 0.1,
+        "multigrid {mg_residual} vs jacobi {jacobi_residual} (initial {initial})"
+    );
+}
+
+#[test]
+fn each_v_cycle_shrinks_the_residual() {
+    let rhs = stroke_like_rhs(200, 120);
+    let settings = VCycleSettings::default();
+    let mut p = Grid::new(200, 120);
+
+    let mut previous = residual(&p, &rhs, 1.0).rms();
+    for cycle in 0..5 {
+        v_cycle(&mut p, &rhs, &settings);
+        let current = residual(&p, &rhs, 1.0).rms();
+        assert!(
+            current < previous * 0.5,
+            "cycle {cycle}: residual went from {previous} to {current}"
+        );
+        previous = current;
+    }
+}
+
+// Error against a converged solution, ignoring the constant the Neumann problem leaves free
+fn error_rms(p: &Grid, exact: &Grid) -> f32 {
+    let n = p.data.len() as f32;
+    let mean_diff: f32 = p
+        .data
+        .iter()
+        .zip(&exact.data)
+        .map(|(a, b)| a - b)
+        .sum::<f32>()
+        / n;
+    let sum_sq: f32 = p
+        .data
+        .iter()
+        .zip(&exact.data)
+        .map(|(a, b)| (a - b - mean_diff).powi(2))
+        .sum();
+    (sum_sq / n).sqrt()
+}
+
+#[test]
+fn red_black_sor_beats_jacobi_at_equal_iterations() {
+    let rhs = stroke_like_rhs(128, 96);
+
+    let mut exact = Grid::new(128, 96);
+    for _ in 0..30 {
+        v_cycle(&mut exact, &rhs, &VCycleSettings::default());
+    }
+
+    let mut p_jacobi = Grid::new(128, 96);
+    jacobi(&mut p_jacobi, &rhs, 40);
+    let jacobi_error = error_rms(&p_jacobi, &exact);
+
+    // Plain Gauss-Seidel already wins; over-relaxation wins by a lot
+    let mut p_gs = Grid::new(128, 96);
+    red_black_sor(&mut p_gs, &rhs, 1.0, 40);
+    let gs_error = error_rms(&p_gs, &exact);
+    assert!(
+        gs_error < jacobi_error,
+        "gauss-seidel {gs_error} vs jacobi {jacobi_error}"
+    );
+
+    let mut p_sor = Grid::new(128, 96);
+    red_black_sor(&mut p_sor, &rhs, 1.8, 40);
+    let sor_error = error_rms(&p_sor, &exact);
+    assert!(
+        sor_error < jacobi_error * 0.75,
+        "SOR {sor_error} vs jacobi {jacobi_error}"
+    );
+}
+
+#[test]
+#[ignore = "needs a graphics adapter (a software one is enough), run with --include-ignored"]
+fn gpu_v_cycle_matches_the_reference() {
+    let (width, height) = (128, 96);
+    let rhs = stroke_like_rhs(width, height);
+    let mut headless =
+        pollster::block_on(HeadlessSim::new(width as u32, height as u32, true)).unwrap();
+
+    // One V-cycle from zero, every edge a wall: the case the reference implements
+    let params = GuiParams {
+        pressure_solver: PressureSolver::Multigrid,
+        multigrid_cycles: 1,
+        adaptive_pressure: false,
+        ..GuiParams::default()
+    };
+    let gpu = Grid {
+        width,
+        height,
+        data: headless.solve_pressure(&params, &rhs.data),
+    };
+    let mut cpu = Grid::new(width, height);
+    v_cycle(&mut cpu, &rhs, &VCycleSettings::default());
+
+    let scale = cpu.data.iter().fold(0.0_f32, |max, p| max.max(p.abs()));
+    let worst = gpu
+        .data
+        .iter()
+        .zip(&cpu.data)
+        .map(|(g, c)| (g - c).abs())
+        .fold(0.0, f32::max);
+    assert!(
+        worst <= scale * 1e-3,
+        "max difference {worst} (pressure up to {scale})"
+    );
+
+    let gpu_residual = residual(&gpu, &rhs, 1.0).rms();
+    let cpu_residual = residual(&cpu, &rhs, 1.0).rms();
+    assert!(
+        (gpu_residual - cpu_residual).abs() <= cpu_residual * 1e-2,
+        "residual {gpu_residual} on the GPU, {cpu_residual} on the CPU"
+    );
+}
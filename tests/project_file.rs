@@ -0,0 +1,190 @@
+// The .fsp project format (io_mod/project.rs): round trip and forward compatibility.
+use fluid_sim_paint::gui_mod::gui::{BoundaryMode, ColorModel, GuiParams, StabilizerMode};
+use fluid_sim_paint::io_mod::project::{
+    MAGIC, Project, ProjectField, VERSION_MAJOR, read_project, write_project,
+};
+
+fn sample_project() -> Project {
+    let (width, height) = (17, 16); // The smallest canvas the panel allows, plus one column
+    let mut params = GuiParams {
+        canvas_width: width,
+        canvas_height: height,
+        color_model: ColorModel::Palette,
+        pigment: 5,
+        stabilizer: StabilizerMode::LazyMouse,
+        brush_color: [0.1, 0.2, 0.3, 0.4],
+        drying_time: 10.0 / 3.0, // No short decimal form
+        paused: true,
+        ..GuiParams::default()
+    };
+    params.view.zoom = 2.75;
+    params.view.rotation = -0.5;
+    params.boundary[2] = BoundaryMode::Periodic;
+    params.boundary[3] = BoundaryMode::Periodic;
+
+    let cells = (width * height) as usize;
+    let field = |name: &str, channels: u32| ProjectField {
+        name: name.to_string(),
+        channels,
+        data: (0..cells * channels as usize)
+            .map(|i| (i as f32 * 0.37).sin() * 1e3)
+            .collect(),
+    };
+    Project {
+        width,
+        height,
+        params,
+        fields: vec![field("density", 4), field("velocity", 2), field("grain", 1)],
+    }
+}
+
+fn chunk(tag: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut bytes = tag.to_vec();
+    bytes.extend((payload.len() as u64).to_le_bytes());
+    bytes.extend(payload);
+    bytes
+}
+
+fn header(width: u32, height: u32) -> Vec<u8> {
+    [width.to_le_bytes(), height.to_le_bytes()].concat()
+}
+
+// A file with just a header and the given settings
+fn settings_only(width: u32, height: u32, settings: &str) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(VERSION_MAJOR.to_le_bytes());
+    bytes.extend(0u16.to_le_bytes());
+    bytes.extend(chunk(b"HEAD", &header(width, height)));
+    bytes.extend(chunk(b"PARM", settings.as_bytes()));
+    bytes
+}
+
+#[test]
+fn project_round_trips_exactly() {
+    let project = sample_project();
+    let mut bytes = Vec::new();
+    write_project(&mut bytes, &project).unwrap();
+    assert_eq!(&bytes[..4], &MAGIC);
+
+    let loaded = read_project(&mut bytes.as_slice()).unwrap();
+    assert_eq!(loaded, project);
+}
+
+#[test]
+fn unknown_chunks_and_settings_are_skipped() {
+    let project = sample_project();
+    let mut bytes = Vec::new();
+    write_project(&mut bytes, &project).unwrap();
+    // What a newer minor version might add
+    bytes.extend(chunk(b"XTRA", &[1, 2, 3]));
+    bytes.extend(chunk(b"PARM", b"future_setting = 42\nbrush_size = 12.5\n"));
+
+    let loaded = read_project(&mut bytes.as_slice()).unwrap();
+    assert_eq!(loaded.fields, project.fields);
+    assert_eq!(loaded.params.brush_size, 12.5);
+    assert_eq!(loaded.params.color_model, ColorModel::Palette);
+}
+
+#[test]
+fn missing_settings_keep_their_defaults() {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(VERSION_MAJOR.to_le_bytes());
+    bytes.extend(0u16.to_le_bytes());
+    bytes.extend(chunk(b"HEAD", &header(24, 16)));
+    bytes.extend(chunk(b"PARM", b"vorticity = 4\ntool = NotATool\n"));
+
+    let loaded = read_project(&mut bytes.as_slice()).unwrap();
+    let defaults = GuiParams::default();
+    assert_eq!((loaded.width, loaded.height), (24, 16));
+    assert_eq!(loaded.params.vorticity, 4.0);
+    assert_eq!(loaded.params.tool, defaults.tool);
+    assert_eq!(loaded.params.viscosity, defaults.viscosity);
+    assert!(loaded.fields.is_empty());
+}
+
+#[test]
+fn newer_major_version_is_refused() {
+    let mut bytes = Vec::new();
+    write_project(&mut bytes, &sample_project()).unwrap();
+    bytes[4..6].copy_from_slice(&(VERSION_MAJOR + 1).to_le_bytes());
+    assert!(read_project(&mut bytes.as_slice()).is_err());
+}
+
+#[test]
+fn truncated_file_is_an_error() {
+    let mut bytes = Vec::new();
+    write_project(&mut bytes, &sample_project()).unwrap();
+    bytes.truncate(bytes.len() - 10);
+    assert!(read_project(&mut bytes.as_slice()).is_err());
+}
+
+#[test]
+fn canvas_sizes_outside_the_panels_range_are_refused() {
+    for (width, height) in [(0, 16), (15, 16), (16, 8193), (u32::MAX, u32::MAX)] {
+        let bytes = settings_only(width, height, "");
+        let error = read_project(&mut bytes.as_slice()).unwrap_err();
+        assert!(format!("{error:#}").contains("canvas size"), "{error:#}");
+    }
+    let bytes = settings_only(16, 8192, "");
+    assert!(read_project(&mut bytes.as_slice()).is_ok());
+}
+
+#[test]
+fn settings_are_clamped_to_the_panels_ranges() {
+    let bytes = settings_only(
+        16,
+        16,
+        "pigment = 8\npressure_iterations = 4000000000\nview.zoom = 0\nsor_omega = -3\n",
+    );
+    let loaded = read_project(&mut bytes.as_slice()).unwrap();
+    assert_eq!(loaded.params.pigment, 7);
+    assert_eq!(loaded.params.pressure_iterations, 200);
+    assert_eq!(loaded.params.view.zoom, 0.1);
+    assert_eq!(loaded.params.sor_omega, 1.0);
+}
+
+#[test]
+fn non_finite_settings_keep_their_defaults() {
+    let bytes = settings_only(
+        16,
+        16,
+        "brush_size = inf\ntime_scale = NaN\nview.pan = 1 -inf\nvorticity = 2\n",
+    );
+    let loaded = read_project(&mut bytes.as_slice()).unwrap();
+    let defaults = GuiParams::default();
+    assert_eq!(loaded.params.brush_size, defaults.brush_size);
+    assert_eq!(loaded.params.time_scale, defaults.time_scale);
+    assert_eq!(loaded.params.view.pan, defaults.view.pan);
+    assert_eq!(loaded.params.vorticity, 2.0);
+}
+
+#[test]
+fn periodic_edges_come_in_pairs() {
+    let bytes = settings_only(
+        16,
+        16,
+        "boundary.left = Wall\nboundary.right = Periodic\nboundary.bottom = Periodic\n",
+    );
+    let loaded = read_project(&mut bytes.as_slice()).unwrap();
+    assert_eq!(loaded.params.boundary, [BoundaryMode::Periodic; 4]);
+
+    let bytes = settings_only(16, 16, "boundary.top = Open\n");
+    let loaded = read_project(&mut bytes.as_slice()).unwrap();
+    let mut expected = GuiParams::default().boundary;
+    expected[2] = BoundaryMode::Open;
+    assert_eq!(loaded.params.boundary, expected);
+}
+
+#[test]
+fn app_preferences_are_not_saved() {
+    let mut project = sample_project();
+    project.params.undo_budget_mb = 2048;
+    project.params.autosave_interval = f32::INFINITY;
+    let mut bytes = Vec::new();
+    write_project(&mut bytes, &project).unwrap();
+
+    let loaded = read_project(&mut bytes.as_slice()).unwrap();
+    let defaults = GuiParams::default();
+    assert_eq!(loaded.params.undo_budget_mb, defaults.undo_budget_mb);
+    assert_eq!(loaded.params.autosave_interval, defaults.autosave_interval);
+}
@@ -0,0 +1,143 @@
+// Crash detection through the session lock files (recovery.rs). Dropping a Recovery without
+// shutdown() is what a crash looks like: the OS lets go of the lock, the files stay.
+use fluid_sim_paint::recovery::Recovery;
+use std::path::{Path, PathBuf};
+
+// An empty directory of the test's own
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "fluid_sim_paint-recovery-{}-{name}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn file_count(dir: &Path) -> usize {
+    std::fs::read_dir(dir).unwrap().count()
+}
+
+// Stands in for a finished autosave; recovery only looks at whether the file is there
+fn fake_autosave(recovery: &Recovery) -> PathBuf {
+    let path = recovery.autosave_path().unwrap();
+    std::fs::write(&path, b"FSP\0").unwrap();
+    path
+}
+
+#[test]
+fn a_crashed_session_is_offered_once() {
+    let dir = test_dir("crash");
+    let crashed = Recovery::start_in(dir.clone());
+    assert!(crashed.previous_session().is_none());
+    let autosave = fake_autosave(&crashed);
+    drop(crashed);
+
+    let mut next = Recovery::start_in(dir.clone());
+    assert_eq!(next.previous_session(), Some(autosave.as_path()));
+
+    // Another instance starting while the question is open doesn't offer it again
+    let mut other = Recovery::start_in(dir.clone());
+    assert!(other.previous_session().is_none());
+
+    next.discard_previous_session();
+    assert!(next.previous_session().is_none());
+    assert!(!autosave.exists());
+
+    next.shutdown();
+    other.shutdown();
+    assert_eq!(file_count(&dir), 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_running_session_is_left_alone() {
+    let dir = test_dir("running");
+    let mut running = Recovery::start_in(dir.clone());
+    let autosave = fake_autosave(&running);
+
+    let mut second = Recovery::start_in(dir.clone());
+    assert!(second.previous_session().is_none());
+
+    // The second one's clean exit only removes its own files
+    second.shutdown();
+    assert!(autosave.exists());
+    assert_eq!(file_count(&dir), 2);
+
+    running.shutdown();
+    assert_eq!(file_count(&dir), 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_crash_before_the_first_autosave_offers_nothing() {
+    let dir = test_dir("no-autosave");
+    drop(Recovery::start_in(dir.clone()));
+    assert_eq!(file_count(&dir), 1); // Only the stale lock
+
+    let mut next = Recovery::start_in(dir.clone());
+    assert!(next.previous_session().is_none());
+    next.shutdown();
+    assert_eq!(file_count(&dir), 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn an_unanswered_recovery_is_kept_for_next_time() {
+    let dir = test_dir("unanswered");
+    let crashed = Recovery::start_in(dir.clone());
+    let autosave = fake_autosave(&crashed);
+    drop(crashed);
+
+    let mut closed_without_answering = Recovery::start_in(dir.clone());
+    assert!(closed_without_answering.previous_session().is_some());
+    closed_without_answering.shutdown();
+
+    let mut next = Recovery::start_in(dir.clone());
+    assert_eq!(next.previous_session(), Some(autosave.as_path()));
+    next.discard_previous_session();
+    next.shutdown();
+    assert_eq!(file_count(&dir), 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_restored_session_survives_a_crash_before_the_next_autosave() {
+    let dir = test_dir("restored");
+    let crashed = Recovery::start_in(dir.clone());
+    let autosave = fake_autosave(&crashed);
+    drop(crashed);
+
+    let mut restoring = Recovery::start_in(dir.clone());
+    assert!(restoring.previous_session().is_some());
+    restoring.restored_previous_session();
+    assert!(restoring.previous_session().is_none());
+    assert!(autosave.exists());
+
+    // Still held: not offered to another instance
+    let mut other = Recovery::start_in(dir.clone());
+    assert!(other.previous_session().is_none());
+    other.shutdown();
+
+    // Crashes again before writing an autosave of its own
+    drop(restoring);
+    let mut next = Recovery::start_in(dir.clone());
+    assert_eq!(next.previous_session(), Some(autosave.as_path()));
+    next.restored_previous_session();
+
+    // A clean exit doesn't need it any more
+    next.shutdown();
+    assert_eq!(file_count(&dir), 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn odd_intervals_never_autosave() {
+    let dir = test_dir("intervals");
+    let mut recovery = Recovery::start_in(dir.clone());
+    // A value from a hand-edited or damaged file must not panic
+    for interval in [0.0, -1.0, f32::INFINITY, f32::NAN, f32::MAX] {
+        assert!(!recovery.autosave_due(interval), "{interval}");
+    }
+    recovery.shutdown();
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,69 @@
+// The brush polyline built from the cursor samples (canvas_mod/stroke.rs).
+use fluid_sim_paint::canvas_mod::stroke::{
+    MAX_STROKE_POINTS, path_length, stroke_bounds, stroke_path,
+};
+
+fn assert_close(a: [f32; 2], b: [f32; 2]) {
+    assert!(
+        (a[0] - b[0]).abs() < 1e-3 && (a[1] - b[1]).abs() < 1e-3,
+        "{a:?} != {b:?}"
+    );
+}
+
+#[test]
+fn passes_through_every_sample() {
+    let samples = [[10.0, 10.0], [20.0, 14.0], [31.0, 9.0], [40.0, 20.0]];
+    let path = stroke_path(&samples);
+
+    assert_eq!(path.first(), samples.first());
+    assert_eq!(path.last(), samples.last());
+    // Catmull-Rom interpolates: each sample is a point of the path, not just near it
+    for sample in &samples[1..3] {
+        assert!(
+            path.iter()
+                .any(|p| (p[0] - sample[0]).abs() < 1e-3 && (p[1] - sample[1]).abs() < 1e-3),
+            "{sample:?} missing from {path:?}"
+        );
+    }
+}
+
+#[test]
+fn straight_samples_stay_straight() {
+    let path = stroke_path(&[[0.0, 5.0], [10.0, 5.0], [20.0, 5.0]]);
+    // Points about two cells apart, all on the line
+    assert!(path.len() >= 11, "{}", path.len());
+    assert!(path.iter().all(|p| (p[1] - 5.0).abs() < 1e-4));
+    assert!(path.windows(2).all(|pair| pair[1][0] > pair[0][0]));
+    assert!((path_length(&path) - 20.0).abs() < 1e-3);
+}
+
+#[test]
+fn a_single_sample_is_a_dot() {
+    assert!(stroke_path(&[]).is_empty());
+    let path = stroke_path(&[[3.0, 4.0]]);
+    assert_eq!(path, [[3.0, 4.0]]);
+    assert_eq!(path_length(&path), 0.0);
+}
+
+#[test]
+fn long_strokes_are_capped_with_both_ends_kept() {
+    // A frame's worth of samples far apart: eight spline points each before thinning
+    let samples: Vec<[f32; 2]> = (0..200)
+        .map(|i| {
+            let t = i as f32 * 0.1;
+            [100.0 + 50.0 * t.cos(), 100.0 + 50.0 * t.sin() + i as f32]
+        })
+        .collect();
+    let path = stroke_path(&samples);
+
+    assert_eq!(path.len(), MAX_STROKE_POINTS);
+    assert_close(path[0], samples[0]);
+    assert_close(*path.last().unwrap(), *samples.last().unwrap());
+}
+
+#[test]
+fn bounds_grow_by_the_radius() {
+    let path = [[10.0, 40.0], [30.0, 20.0], [25.0, 50.0]];
+    assert_eq!(stroke_bounds(&path, 5.0), [5.0, 15.0, 35.0, 55.0]);
+    assert_eq!(stroke_bounds(&[[7.0, 8.0]], 2.0), [5.0, 6.0, 9.0, 10.0]);
+}
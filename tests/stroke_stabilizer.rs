@@ -0,0 +1,85 @@
+// Brush smoothing ahead of the sim (stabilizer.rs).
+use fluid_sim_paint::gui_mod::gui::StabilizerMode;
+use fluid_sim_paint::stabilizer::StrokeStabilizer;
+
+const FRAME: f32 = 1.0 / 60.0;
+
+fn assert_close(a: [f32; 2], b: [f32; 2]) {
+    assert!(
+        (a[0] - b[0]).abs() < 1e-3 && (a[1] - b[1]).abs() < 1e-3,
+        "{a:?} != {b:?}"
+    );
+}
+
+#[test]
+fn off_follows_the_mouse() {
+    let mut stabilizer = StrokeStabilizer::default();
+    stabilizer.update([0.0, 0.0], true, StabilizerMode::Off, 1.0, FRAME);
+    let pos = stabilizer.update([123.0, -4.0], true, StabilizerMode::Off, 1.0, FRAME);
+    assert_close(pos, [123.0, -4.0]);
+}
+
+#[test]
+fn lazy_mouse_waits_for_the_string_to_go_taut() {
+    // Strength 0.5 is a 40 pixel string
+    let mut stabilizer = StrokeStabilizer::default();
+    let lazy = |stabilizer: &mut StrokeStabilizer, target| {
+        stabilizer.update(target, true, StabilizerMode::LazyMouse, 0.5, FRAME)
+    };
+
+    // The stroke starts exactly where it was clicked
+    assert_close(lazy(&mut stabilizer, [100.0, 100.0]), [100.0, 100.0]);
+    // Within the string's length the brush stays put
+    assert_close(lazy(&mut stabilizer, [130.0, 100.0]), [100.0, 100.0]);
+    assert_close(lazy(&mut stabilizer, [100.0, 139.0]), [100.0, 100.0]);
+    // Beyond it the brush is dragged along, one string length behind
+    assert_close(lazy(&mut stabilizer, [150.0, 100.0]), [110.0, 100.0]);
+    assert_close(lazy(&mut stabilizer, [110.0, 200.0]), [110.0, 160.0]);
+}
+
+#[test]
+fn smooth_converges_on_the_target() {
+    let mut stabilizer = StrokeStabilizer::default();
+    let smooth = |stabilizer: &mut StrokeStabilizer, target, elapsed| {
+        stabilizer.update(target, true, StabilizerMode::Smooth, 1.0, elapsed)
+    };
+
+    smooth(&mut stabilizer, [0.0, 0.0], FRAME);
+    let mut previous = 0.0;
+    for _ in 0..120 {
+        let pos = smooth(&mut stabilizer, [100.0, 0.0], FRAME);
+        // Moves towards the target every frame, never past it
+        assert!(pos[0] > previous && pos[0] <= 100.0, "{pos:?}");
+        assert_close([0.0, pos[1]], [0.0, 0.0]);
+        previous = pos[0];
+    }
+    // Two seconds is eight time constants at full strength
+    assert!(previous > 99.9, "{previous}");
+}
+
+#[test]
+fn smooth_does_not_depend_on_the_frame_rate() {
+    let mut fast = StrokeStabilizer::default();
+    let mut slow = StrokeStabilizer::default();
+    fast.update([0.0, 0.0], true, StabilizerMode::Smooth, 0.5, FRAME);
+    slow.update([0.0, 0.0], true, StabilizerMode::Smooth, 0.5, FRAME);
+
+    let mut fast_pos = [0.0; 2];
+    for _ in 0..4 {
+        fast_pos = fast.update([50.0, 50.0], true, StabilizerMode::Smooth, 0.5, FRAME);
+    }
+    let slow_pos = slow.update([50.0, 50.0], true, StabilizerMode::Smooth, 0.5, 4.0 * FRAME);
+    assert_close(fast_pos, slow_pos);
+}
+
+#[test]
+fn releasing_the_button_drops_the_smoothed_position() {
+    let mut stabilizer = StrokeStabilizer::default();
+    stabilizer.update([0.0, 0.0], true, StabilizerMode::Smooth, 1.0, FRAME);
+    stabilizer.update([100.0, 0.0], true, StabilizerMode::Smooth, 1.0, FRAME);
+    // Hovering while up sits on the mouse, so the next stroke starts where it's clicked
+    let hover = stabilizer.update([300.0, 40.0], false, StabilizerMode::Smooth, 1.0, FRAME);
+    assert_close(hover, [300.0, 40.0]);
+    let start = stabilizer.update([300.0, 40.0], true, StabilizerMode::Smooth, 1.0, FRAME);
+    assert_close(start, [300.0, 40.0]);
+}
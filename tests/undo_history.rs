@@ -0,0 +1,121 @@
+// Undo/redo bookkeeping and the memory budget (canvas_mod/history.rs), with sizes standing
+// in for the GPU snapshots.
+use fluid_sim_paint::canvas_mod::history::{History, HistoryEntry};
+
+// A snapshot of 'bytes', told apart by 'id'
+#[derive(Debug, PartialEq)]
+struct Entry {
+    id: u32,
+    bytes: u64,
+}
+
+impl HistoryEntry for Entry {
+    fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+fn entry(id: u32) -> Entry {
+    Entry { id, bytes: 10 }
+}
+
+const PLENTY: u64 = 1_000;
+
+#[test]
+fn undo_and_redo_swap_with_the_current_state() {
+    let mut history = History::default();
+    history.record(entry(0), PLENTY);
+    history.record(entry(1), PLENTY);
+    assert_eq!(history.steps(), (2, 0));
+
+    // The live state (2) is kept for redo, the last recorded one comes back
+    assert_eq!(history.step_back(PLENTY, || entry(2)), Some(entry(1)));
+    assert_eq!(history.steps(), (1, 1));
+    assert_eq!(history.step_forward(PLENTY, || entry(1)), Some(entry(2)));
+    assert_eq!(history.steps(), (2, 0));
+
+    // Nothing to redo: the current state isn't captured
+    assert_eq!(
+        history.step_forward(PLENTY, || panic!("captured for nothing")),
+        None
+    );
+}
+
+#[test]
+fn a_new_change_drops_the_redo_steps() {
+    let mut history = History::default();
+    history.record(entry(0), PLENTY);
+    history.step_back(PLENTY, || entry(1));
+    assert_eq!(history.steps(), (0, 1));
+    history.record(entry(2), PLENTY);
+    assert_eq!(history.steps(), (1, 0));
+}
+
+#[test]
+fn recording_evicts_the_oldest_undo_steps() {
+    let mut history = History::default();
+    for id in 0..5 {
+        history.record(entry(id), 30);
+    }
+    assert_eq!(history.steps(), (3, 0));
+    assert_eq!(history.bytes(), 30);
+    // What's left are the newest steps
+    assert_eq!(history.step_back(30, || entry(5)), Some(entry(4)));
+}
+
+#[test]
+fn undo_and_redo_stay_within_the_budget() {
+    let mut history = History::default();
+    for id in 0..3 {
+        history.record(entry(id), 30);
+    }
+
+    // The live state is bigger (e.g. a second pigment field): the oldest undo step makes room
+    history.step_back(30, || Entry { id: 3, bytes: 20 });
+    assert_eq!(history.steps(), (1, 1));
+    assert_eq!(history.bytes(), 30);
+
+    history.step_forward(30, || entry(2));
+    assert_eq!(history.steps(), (2, 0));
+
+    // Cycling back and forth never goes over
+    for id in 10..20 {
+        history.step_back(30, || Entry { id, bytes: 20 });
+        assert!(history.bytes() <= 30, "{}", history.bytes());
+        history.step_forward(30, || entry(id));
+        assert!(history.bytes() <= 30, "{}", history.bytes());
+    }
+}
+
+#[test]
+fn the_furthest_redo_steps_go_once_the_undo_side_is_down_to_one() {
+    let mut history = History::default();
+    for id in 0..4 {
+        history.record(entry(id), PLENTY);
+    }
+    for id in 4..7 {
+        history.step_back(PLENTY, || entry(id));
+    }
+    assert_eq!(history.steps(), (1, 3));
+
+    // A smaller budget: the undo side can't shrink, so the redo step undone first goes
+    history.step_back(20, || entry(7));
+    assert_eq!(history.steps(), (0, 2));
+    assert_eq!(history.step_forward(20, || entry(0)), Some(entry(7)));
+    assert_eq!(history.step_forward(20, || entry(1)), Some(entry(6)));
+    assert_eq!(history.step_forward(20, || entry(2)), None);
+}
+
+#[test]
+fn one_step_each_way_survives_any_budget() {
+    let mut history = History::default();
+    history.record(entry(0), 0);
+    history.record(entry(1), 0);
+    assert_eq!(history.steps(), (1, 0));
+    history.step_back(0, || entry(2));
+    assert_eq!(history.steps(), (0, 1));
+
+    history.clear();
+    assert_eq!(history.steps(), (0, 0));
+    assert_eq!(history.bytes(), 0);
+}
@@ -0,0 +1,72 @@
+// The screen <-> grid mapping shared by the renderer and the brush (canvas_mod/view.rs).
+use fluid_sim_paint::canvas_mod::view::ViewTransform;
+
+const SCREEN: (u32, u32) = (1280, 720);
+const GRID: (u32, u32) = (512, 384);
+
+fn assert_close(a: [f32; 2], b: [f32; 2]) {
+    assert!(
+        (a[0] - b[0]).abs() < 1e-3 && (a[1] - b[1]).abs() < 1e-3,
+        "{a:?} != {b:?}"
+    );
+}
+
+#[test]
+fn screen_and_grid_round_trip() {
+    let view = ViewTransform {
+        pan: [37.0, -12.5],
+        zoom: 2.5,
+        rotation: 0.7,
+    };
+    for screen_pos in [[0.0, 0.0], [640.0, 360.0], [1001.0, 17.0]] {
+        let grid_pos = view.screen_to_grid(screen_pos, SCREEN, GRID);
+        assert_close(view.grid_to_screen(grid_pos, SCREEN, GRID), screen_pos);
+    }
+    // The canvas centre sits at the screen centre plus the pan
+    assert_close(
+        view.grid_to_screen([256.0, 192.0], SCREEN, GRID),
+        [677.0, 347.5],
+    );
+}
+
+#[test]
+fn zoom_keeps_the_cell_under_the_cursor() {
+    let mut view = ViewTransform {
+        pan: [20.0, 30.0],
+        zoom: 1.5,
+        rotation: -2.0,
+    };
+    let cursor = [900.0, 200.0];
+    let before = view.screen_to_grid(cursor, SCREEN, GRID);
+    view.zoom_at(cursor, 1.1_f32.powi(5), SCREEN);
+    assert_close(view.screen_to_grid(cursor, SCREEN, GRID), before);
+}
+
+#[test]
+fn rotation_turns_the_canvas_clockwise_about_its_centre() {
+    let mut view = ViewTransform::default();
+    view.rotate_by(std::f32::consts::FRAC_PI_2);
+    // The canvas's right edge ends up below its centre (screen y points down)
+    assert_close(
+        view.grid_to_screen([356.0, 192.0], SCREEN, GRID),
+        [640.0, 460.0],
+    );
+    // A stroke to the right on screen runs toward grid row 0, i.e. "up" the paper
+    let from = view.screen_to_grid([600.0, 300.0], SCREEN, GRID);
+    let to = view.screen_to_grid([700.0, 300.0], SCREEN, GRID);
+    assert_close([to[0] - from[0], to[1] - from[1]], [0.0, -100.0]);
+
+    view.rotate_by(std::f32::consts::PI);
+    assert!((view.rotation + std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+}
+
+#[test]
+fn fit_shows_the_whole_canvas() {
+    let mut view = ViewTransform::default();
+    view.fit(SCREEN, GRID);
+    let top_left = view.grid_to_screen([0.0, 0.0], SCREEN, GRID);
+    let bottom_right = view.grid_to_screen([GRID.0 as f32, GRID.1 as f32], SCREEN, GRID);
+    // Height is the tight side here: edge to edge vertically, inside horizontally
+    assert_close([top_left[1], bottom_right[1]], [0.0, 720.0]);
+    assert!(top_left[0] >= 0.0 && bottom_right[0] <= 1280.0);
+}